@@ -0,0 +1,102 @@
+// 安全保存：先写入同一目录下的临时文件并同步到磁盘，再用 rename 原子地替换目标文件，
+// 写入过程中程序崩溃或断电时，原文件要么是旧内容，要么是完整的新内容
+// 可以在替换前把原文件复制为轮换的备份：file.txt.bak（最新）、file.txt.bak.2 …… file.txt.bak.N
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::{CommandError, IoOperation};
+
+// 同一进程内区分临时文件名
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 第 n 个备份的路径（从 1 开始）
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    if n > 1 {
+        name.push(format!(".{}", n));
+    }
+    path.with_file_name(name)
+}
+
+// 轮换备份：丢弃最旧的，其余依次后移，再把当前文件复制为最新的备份
+fn rotate_backups(path: &Path, count: usize) -> std::io::Result<()> {
+    let _ = fs::remove_file(backup_path(path, count));
+    for n in (1..count).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+// 目录只读（或没有写入权限）时给出专门的错误，其他错误按写入失败报告
+fn write_error(path: &Path, dir: &Path, error: std::io::Error) -> CommandError {
+    match error.kind() {
+        ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => {
+            CommandError::ReadOnlyDirectory {
+                path: dir.display().to_string(),
+            }
+        }
+        _ => CommandError::io(IoOperation::Write, path, error),
+    }
+}
+
+// 原子地把 contents 写入 path，保留原文件的权限；backups 为保留的备份个数，0 表示不备份
+// path 是符号链接时替换链接指向的文件，链接本身保持不变
+pub fn write_file(path: &Path, contents: &[u8], backups: usize) -> Result<(), CommandError> {
+    let is_link = fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink());
+    let path = if is_link {
+        fs::canonicalize(path).map_err(|e| CommandError::io(IoOperation::Write, path, e))?
+    } else {
+        path.to_path_buf()
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let original = fs::metadata(&path).ok();
+
+    let temp = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        path.file_name().unwrap_or_default().to_string_lossy(),
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        file.write_all(contents)?;
+        if let Some(original) = &original {
+            file.set_permissions(original.permissions())?;
+        }
+        file.sync_all()
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(write_error(&path, &dir, e));
+    }
+
+    // 备份失败不影响保存，原文件在替换前一直完好
+    if backups > 0 && original.is_some() {
+        if let Err(e) = rotate_backups(&path, backups) {
+            eprintln!("[RUST] 备份 {} 失败: {:?}", path.display(), e);
+        }
+    }
+
+    if let Err(e) = fs::rename(&temp, &path) {
+        let _ = fs::remove_file(&temp);
+        return Err(write_error(&path, &dir, e));
+    }
+    // 同步目录，确保 rename 本身也已写入磁盘（Windows 上不能这样打开目录，忽略错误）
+    if let Ok(dir) = File::open(&dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
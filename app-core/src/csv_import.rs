@@ -0,0 +1,453 @@
+// CSV / TSV 导入：自动识别分隔符和表头，按列计算描述性统计
+// 无法解析的单元格会连同所在行号一起返回，方便用户回到表格中修正
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{CommandError, IoOperation, Resource};
+use crate::i18n;
+use crate::statistics::{self, Statistics, StatisticsOptions};
+
+// 用于自动识别的候选分隔符
+const CANDIDATE_DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+// 自动识别时最多检查的行数
+const SNIFF_LINES: usize = 20;
+
+// 选择列：既可以用从 0 开始的列序号，也可以用表头中的列名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ColumnSelector {
+    Index(usize),
+    Name(String),
+}
+
+// 导入选项，所有字段都可以省略
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvImportOptions {
+    // 分隔符，省略时自动识别
+    pub delimiter: Option<char>,
+    // 第一行是否为表头，省略时自动识别
+    pub has_header: Option<bool>,
+    // 需要统计的列，省略时统计所有包含数字的列
+    #[serde(default)]
+    pub columns: Vec<ColumnSelector>,
+    #[serde(default)]
+    pub statistics: StatisticsOptions,
+}
+
+// 单元格无法解析的原因，序列化为稳定的原因码（如 "NotANumber"）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum InvalidCellReason {
+    // 该行的列数不够
+    MissingCell,
+    EmptyCell,
+    NotANumber,
+}
+
+impl InvalidCellReason {
+    fn message_key(self) -> &'static str {
+        match self {
+            InvalidCellReason::MissingCell => "csv.missingCell",
+            InvalidCellReason::EmptyCell => "csv.emptyCell",
+            InvalidCellReason::NotANumber => "csv.notANumber",
+        }
+    }
+}
+
+// 无法解析的单元格：reason 为原因码，message 为按当前语言生成的说明
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidCell {
+    // 文件中的行号（从 1 开始）
+    pub line: u64,
+    pub value: String,
+    pub reason: InvalidCellReason,
+    pub message: String,
+}
+
+impl InvalidCell {
+    fn new(line: u64, value: String, reason: InvalidCellReason) -> Self {
+        InvalidCell {
+            line,
+            value,
+            reason,
+            message: i18n::t(reason.message_key()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnAnalysis {
+    pub index: usize,
+    pub name: String,
+    // 列中没有可统计的数字时为 None，原因见 error
+    pub statistics: Option<Statistics>,
+    pub error: Option<CommandError>,
+    pub invalid_cells: Vec<InvalidCell>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvAnalysis {
+    pub delimiter: char,
+    pub has_header: bool,
+    pub headers: Vec<String>,
+    // 数据行数（不含表头）
+    pub row_count: usize,
+    pub columns: Vec<ColumnAnalysis>,
+}
+
+// 只接受有限的数字："nan"、"inf"、"infinity" 和溢出的 "1e400" 也能被 f64 解析，
+// 这里把它们当作非数字单元格，连同行号一起报告
+fn parse_number(cell: &str) -> Option<f64> {
+    cell.trim().parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+// 粗略地统计一行中分隔符出现的次数，忽略双引号内的字符
+fn count_outside_quotes(line: &str, delimiter: char) -> usize {
+    let mut in_quotes = false;
+    let mut count = 0;
+    for c in line.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == delimiter && !in_quotes {
+            count += 1;
+        }
+    }
+    count
+}
+
+// 识别分隔符：优先选择在前若干行中出现次数一致且最多的字符
+fn detect_delimiter(text: &str, path: &Path) -> char {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(SNIFF_LINES)
+        .collect();
+
+    let mut best: Option<(char, bool, usize)> = None;
+    for &delimiter in &CANDIDATE_DELIMITERS {
+        let counts: Vec<usize> = lines
+            .iter()
+            .map(|line| count_outside_quotes(line, delimiter))
+            .collect();
+        let first = counts.first().copied().unwrap_or(0);
+        if first == 0 {
+            continue;
+        }
+        let consistent = counts.iter().all(|&c| c == first);
+        let better = match best {
+            None => true,
+            Some((_, best_consistent, best_count)) => {
+                (consistent, first) > (best_consistent, best_count)
+            }
+        };
+        if better {
+            best = Some((delimiter, consistent, first));
+        }
+    }
+
+    match best {
+        Some((delimiter, _, _)) => delimiter,
+        None => {
+            let is_tsv = path
+                .extension()
+                .map(|ext| ext.eq_ignore_ascii_case("tsv"))
+                .unwrap_or(false);
+            if is_tsv {
+                '\t'
+            } else {
+                ','
+            }
+        }
+    }
+}
+
+// 识别表头：第一行存在非数字单元格，而第二行同一列是数字时，认为第一行是表头
+fn detect_header(rows: &[csv::StringRecord]) -> bool {
+    let Some(first) = rows.first() else {
+        return false;
+    };
+    let non_numeric = |cell: &str| !cell.trim().is_empty() && parse_number(cell).is_none();
+
+    match rows.get(1) {
+        Some(second) => first.iter().enumerate().any(|(i, cell)| {
+            non_numeric(cell) && second.get(i).and_then(parse_number).is_some()
+        }),
+        None => first.iter().all(non_numeric),
+    }
+}
+
+// 读取并分析 CSV / TSV 文件
+pub fn analyze(path: &Path, options: &CsvImportOptions) -> Result<CsvAnalysis, CommandError> {
+    let bytes = fs::read(path).map_err(|e| CommandError::io(IoOperation::Read, path, e))?;
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+
+    let delimiter = options
+        .delimiter
+        .unwrap_or_else(|| detect_delimiter(text, path));
+    if !delimiter.is_ascii() {
+        return Err(CommandError::invalid_argument(
+            "delimiter",
+            format!("{:?}", delimiter),
+            "ASCII",
+        ));
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    // 记录每一行在文件中的行号；csv 自带的行号不计空行，这里按字节偏移自己计算
+    let bytes = text.as_bytes();
+    let mut rows = Vec::new();
+    let mut lines = Vec::new();
+    let (mut scanned, mut line) = (0, 1);
+    for record in reader.records() {
+        let record = record.map_err(|e| CommandError::ParseFailed {
+            format: "csv".to_string(),
+            reason: e.to_string(),
+        })?;
+        // 跳过完全空白的行
+        if record.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        // 记录的起始偏移可能落在前面的空行上，先跳过换行符
+        let mut offset = record.position().map(|p| p.byte() as usize).unwrap_or(0);
+        while offset < bytes.len() && (bytes[offset] == b'\n' || bytes[offset] == b'\r') {
+            offset += 1;
+        }
+        line += bytes[scanned..offset].iter().filter(|&&b| b == b'\n').count() as u64;
+        scanned = offset;
+        lines.push(line);
+        rows.push(record);
+    }
+
+    let has_header = options.has_header.unwrap_or_else(|| detect_header(&rows));
+    let column_count = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let headers: Vec<String> = if has_header && !rows.is_empty() {
+        let header = rows.remove(0);
+        lines.remove(0);
+        (0..column_count)
+            .map(|i| header.get(i).map(|h| h.trim().to_string()).unwrap_or_default())
+            .collect()
+    } else {
+        (0..column_count)
+            .map(|i| i18n::t_with("csv.defaultHeader", json!({ "position": i + 1 })))
+            .collect()
+    };
+
+    // 确定需要统计的列
+    let selected: Vec<usize> = if options.columns.is_empty() {
+        (0..column_count)
+            .filter(|&i| rows.iter().any(|row| row.get(i).and_then(parse_number).is_some()))
+            .collect()
+    } else {
+        options
+            .columns
+            .iter()
+            .map(|selector| match selector {
+                ColumnSelector::Index(i) if *i < column_count => Ok(*i),
+                ColumnSelector::Index(i) => Err(CommandError::invalid_argument(
+                    "columns",
+                    i,
+                    format!("0..{}", column_count),
+                )),
+                ColumnSelector::Name(name) => headers
+                    .iter()
+                    .position(|h| h == name.trim())
+                    .ok_or_else(|| CommandError::NotFound {
+                        resource: Resource::Column,
+                        id: name.clone(),
+                    }),
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    let columns = selected
+        .into_iter()
+        .map(|index| {
+            let mut values = Vec::new();
+            let mut invalid_cells = Vec::new();
+            for (row, &line) in rows.iter().zip(&lines) {
+                let (value, reason) = match row.get(index) {
+                    None => (String::new(), InvalidCellReason::MissingCell),
+                    Some(cell) if cell.trim().is_empty() => {
+                        (String::new(), InvalidCellReason::EmptyCell)
+                    }
+                    Some(cell) => match parse_number(cell) {
+                        Some(value) => {
+                            values.push(value);
+                            continue;
+                        }
+                        None => (cell.to_string(), InvalidCellReason::NotANumber),
+                    },
+                };
+                invalid_cells.push(InvalidCell::new(line, value, reason));
+            }
+
+            let (statistics, error) = match statistics::compute(&values, &options.statistics) {
+                Ok(stats) => (Some(stats), None),
+                Err(e) => (None, Some(e)),
+            };
+            ColumnAnalysis {
+                index,
+                name: headers[index].clone(),
+                statistics,
+                error,
+                invalid_cells,
+            }
+        })
+        .collect();
+
+    Ok(CsvAnalysis {
+        delimiter,
+        has_header,
+        headers,
+        row_count: rows.len(),
+        columns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // 把内容写入临时目录下的独立文件，测试结束时删除
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("app-core-csv-{}-{}", std::process::id(), name));
+            fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn analyze_text(name: &str, contents: &str, options: &CsvImportOptions) -> CsvAnalysis {
+        let file = TempFile::new(name, contents);
+        analyze(&file.0, options).unwrap()
+    }
+
+    #[test]
+    fn detects_delimiter_and_header() {
+        let analysis = analyze_text(
+            "semicolon.csv",
+            "name;score\na;1\nb;2\nc;3\n",
+            &CsvImportOptions::default(),
+        );
+        assert_eq!(analysis.delimiter, ';');
+        assert!(analysis.has_header);
+        assert_eq!(analysis.headers, vec!["name", "score"]);
+        assert_eq!(analysis.row_count, 3);
+        assert_eq!(analysis.columns.len(), 1);
+        let stats = analysis.columns[0].statistics.as_ref().unwrap();
+        assert_eq!((stats.count, stats.sum), (3, 6.0));
+    }
+
+    #[test]
+    fn reports_non_finite_cells_with_line_numbers() {
+        let analysis = analyze_text(
+            "non-finite.tsv",
+            "value\n1\nnan\n\ninf\n-Infinity\n1e400\n\n2\n",
+            &CsvImportOptions::default(),
+        );
+        let column = &analysis.columns[0];
+        let invalid: Vec<(u64, &str)> = column
+            .invalid_cells
+            .iter()
+            .map(|cell| {
+                assert_eq!(cell.reason, InvalidCellReason::NotANumber);
+                (cell.line, cell.value.as_str())
+            })
+            .collect();
+        assert_eq!(
+            invalid,
+            vec![(3, "nan"), (5, "inf"), (6, "-Infinity"), (7, "1e400")]
+        );
+        let stats = column.statistics.as_ref().unwrap();
+        assert_eq!((stats.count, stats.sum), (2, 3.0));
+    }
+
+    #[test]
+    fn reports_missing_and_empty_cells() {
+        let analysis = analyze_text(
+            "cells.csv",
+            "a,b\n1,2\n3\n4, \n",
+            &CsvImportOptions::default(),
+        );
+        let column = &analysis.columns[1];
+        let reasons: Vec<(u64, InvalidCellReason)> = column
+            .invalid_cells
+            .iter()
+            .map(|cell| (cell.line, cell.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (3, InvalidCellReason::MissingCell),
+                (4, InvalidCellReason::EmptyCell)
+            ]
+        );
+    }
+
+    #[test]
+    fn selects_columns_by_name_and_index() {
+        let text = "x,y\n1,10\n2,20\n";
+        let options = CsvImportOptions {
+            columns: vec![ColumnSelector::Name("y".to_string())],
+            ..Default::default()
+        };
+        let analysis = analyze_text("by-name.csv", text, &options);
+        assert_eq!(analysis.columns[0].index, 1);
+
+        let file = TempFile::new("missing.csv", text);
+        let options = CsvImportOptions {
+            columns: vec![ColumnSelector::Name("z".to_string())],
+            ..Default::default()
+        };
+        assert!(matches!(
+            analyze(&file.0, &options),
+            Err(CommandError::NotFound { .. })
+        ));
+        let options = CsvImportOptions {
+            columns: vec![ColumnSelector::Index(2)],
+            ..Default::default()
+        };
+        assert!(matches!(
+            analyze(&file.0, &options),
+            Err(CommandError::InvalidArgument { name, .. }) if name == "columns"
+        ));
+    }
+
+    #[test]
+    fn column_without_numbers_reports_empty_input() {
+        let options = CsvImportOptions {
+            has_header: Some(true),
+            columns: vec![ColumnSelector::Index(0)],
+            ..Default::default()
+        };
+        let analysis = analyze_text("text-only.csv", "label\nnan\ninf\n", &options);
+        let column = &analysis.columns[0];
+        assert!(column.statistics.is_none());
+        assert!(matches!(
+            column.error,
+            Some(CommandError::EmptyInput { .. })
+        ));
+        assert_eq!(column.invalid_cells.len(), 2);
+    }
+}
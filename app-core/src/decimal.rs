@@ -0,0 +1,220 @@
+// 十进制高精度计算：基于 rust_decimal（96 位尾数，最多 28 位小数）
+// 避免 f64 的二进制误差，例如 0.1 + 0.2 会得到精确的 0.3
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CommandError, ValueSource};
+
+// 允许的最大小数位数（rust_decimal 的上限）
+pub const MAX_PRECISION: u32 = 28;
+// 未指定精度时保留的小数位数
+pub const DEFAULT_PRECISION: u32 = 10;
+
+// 舍入模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RoundingMode {
+    // 四舍六入五成双（银行家舍入）
+    #[default]
+    HalfEven,
+    // 四舍五入（.5 远离零）
+    HalfUp,
+    // 直接截断（向零舍入）
+    Truncate,
+}
+
+impl RoundingMode {
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            RoundingMode::HalfEven => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::Truncate => RoundingStrategy::ToZero,
+        }
+    }
+}
+
+// 调用方可选的精度设置，两个字段都可以省略
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecimalOptions {
+    // 结果保留的小数位数（0 ~ 28）
+    pub precision: Option<u32>,
+    #[serde(default)]
+    pub rounding: RoundingMode,
+}
+
+// 解析十进制字符串，支持普通写法（"0.1"）和科学计数法（"1e-3"）
+// 输入必须能被精确表示：超出 Decimal 的范围或多于 28 位小数时返回 OutOfRange，不会悄悄舍入
+pub fn parse_decimal(input: &str) -> Result<Decimal, CommandError> {
+    let text = input.trim();
+    // 科学计数法的尾数同样要求精确
+    let mantissa = text.split(['e', 'E']).next().unwrap_or_default();
+    let parsed = Decimal::from_str_exact(mantissa).and_then(|value| {
+        if mantissa.len() == text.len() {
+            Ok(value)
+        } else {
+            Decimal::from_scientific(text)
+        }
+    });
+    parsed.map_err(|e| match e {
+        rust_decimal::Error::ExceedsMaximumPossibleValue
+        | rust_decimal::Error::LessThanMinimumPossibleValue
+        | rust_decimal::Error::Underflow
+        | rust_decimal::Error::ScaleExceedsMaximumPrecision(_) => CommandError::OutOfRange {
+            source: ValueSource::Input,
+        },
+        // 整数部分位数过多时 rust_decimal 只返回一条文字说明；写法合法的数字都归为超出范围
+        _ if text.parse::<f64>().is_ok_and(f64::is_finite) => CommandError::OutOfRange {
+            source: ValueSource::Input,
+        },
+        _ => CommandError::InvalidNumber {
+            index: None,
+            value: Some(input.to_string()),
+        },
+    })
+}
+
+// 执行一次十进制运算，并按指定精度和舍入模式返回精确的十进制字符串
+pub fn calculate(
+    operation: &str,
+    a: &str,
+    b: &str,
+    options: DecimalOptions,
+) -> Result<String, CommandError> {
+    let precision = options.precision.unwrap_or(DEFAULT_PRECISION);
+    // rust_decimal 最多只能表示 28 位小数，更高的精度没有意义
+    if precision > MAX_PRECISION {
+        return Err(CommandError::invalid_argument(
+            "precision",
+            precision,
+            format!("0..={}", MAX_PRECISION),
+        ));
+    }
+
+    let a = parse_decimal(a)?;
+    let b = parse_decimal(b)?;
+
+    let result = match operation {
+        "add" => a.checked_add(b),
+        "subtract" => a.checked_sub(b),
+        "multiply" => a.checked_mul(b),
+        "divide" => {
+            // 处理除零错误
+            if b.is_zero() {
+                return Err(CommandError::DivisionByZero);
+            }
+            a.checked_div(b)
+        }
+        _ => {
+            return Err(CommandError::UnsupportedOperation {
+                operation: operation.to_string(),
+            })
+        }
+    }
+    .ok_or(CommandError::OutOfRange {
+        source: ValueSource::Result,
+    })?;
+
+    let rounded = result.round_dp_with_strategy(precision, options.rounding.strategy());
+    Ok(rounded.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(operation: &str, a: &str, b: &str, precision: u32, rounding: RoundingMode) -> String {
+        let options = DecimalOptions {
+            precision: Some(precision),
+            rounding,
+        };
+        calculate(operation, a, b, options).unwrap()
+    }
+
+    fn is_out_of_input_range(result: Result<Decimal, CommandError>) -> bool {
+        matches!(
+            result,
+            Err(CommandError::OutOfRange {
+                source: ValueSource::Input
+            })
+        )
+    }
+
+    #[test]
+    fn adds_without_binary_error() {
+        let result = calculate("add", "0.1", "0.2", DecimalOptions::default()).unwrap();
+        assert_eq!(result, "0.3");
+        let result = calculate("divide", "1", "3", DecimalOptions::default()).unwrap();
+        assert_eq!(result, "0.3333333333");
+    }
+
+    #[test]
+    fn parses_plain_and_scientific_notation() {
+        assert_eq!(parse_decimal(" 1.50 ").unwrap().to_string(), "1.50");
+        assert_eq!(parse_decimal("1e-3").unwrap().to_string(), "0.001");
+        assert_eq!(parse_decimal("2.5E2").unwrap().to_string(), "250");
+        for text in ["abc", "inf", "1..2", ""] {
+            assert!(matches!(
+                parse_decimal(text),
+                Err(CommandError::InvalidNumber { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_inputs_that_cannot_be_represented_exactly() {
+        // 29 位小数
+        assert!(is_out_of_input_range(parse_decimal(
+            "0.12345678901234567890123456789"
+        )));
+        // 超过 Decimal::MAX（约 7.9e28）
+        assert!(is_out_of_input_range(parse_decimal(
+            "123456789012345678901234567890"
+        )));
+        assert!(is_out_of_input_range(parse_decimal("1e-29")));
+        assert!(is_out_of_input_range(parse_decimal(
+            "0.12345678901234567890123456789e1"
+        )));
+    }
+
+    #[test]
+    fn applies_rounding_modes() {
+        let cases = [
+            ("2.5", RoundingMode::HalfEven, "2"),
+            ("3.5", RoundingMode::HalfEven, "4"),
+            ("2.5", RoundingMode::HalfUp, "3"),
+            ("-2.5", RoundingMode::HalfUp, "-3"),
+            ("2.7", RoundingMode::Truncate, "2"),
+            ("-2.7", RoundingMode::Truncate, "-2"),
+        ];
+        for (value, rounding, expected) in cases {
+            assert_eq!(
+                run("add", value, "0", 0, rounding),
+                expected,
+                "{:?}",
+                rounding
+            );
+        }
+        assert_eq!(run("divide", "2", "3", 4, RoundingMode::HalfUp), "0.6667");
+    }
+
+    #[test]
+    fn validates_precision_and_operation() {
+        let options = DecimalOptions {
+            precision: Some(MAX_PRECISION + 1),
+            rounding: RoundingMode::HalfEven,
+        };
+        assert!(matches!(
+            calculate("add", "1", "2", options),
+            Err(CommandError::InvalidArgument { name, .. }) if name == "precision"
+        ));
+        assert!(matches!(
+            calculate("divide", "1", "0", DecimalOptions::default()),
+            Err(CommandError::DivisionByZero)
+        ));
+        assert!(matches!(
+            calculate("power", "1", "2", DecimalOptions::default()),
+            Err(CommandError::UnsupportedOperation { .. })
+        ));
+    }
+}
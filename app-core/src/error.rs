@@ -0,0 +1,451 @@
+// 所有 Tauri 命令统一使用的错误类型
+// 序列化给前端的格式为 { code, message, details }：
+//   code    —— 稳定的机器可读错误码（如 "DivisionByZero"），前端应根据它做分支判断
+//   message —— 按当前语言本地化后的提示文字，只用于展示
+//   details —— 与错误相关的结构化信息（如出错的列号、参数名等）
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+
+use crate::i18n::{self, Locale};
+
+// OutOfRange 中超出范围的是输入值还是计算结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValueSource {
+    Input,
+    Result,
+}
+
+// NotFound 中找不到的资源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Resource {
+    HistoryEntry,
+    StatsSession,
+    Column,
+    MenuItem,
+    Action,
+    ClipboardEntry,
+    Document,
+    RecoverySnapshot,
+}
+
+// Io 中失败的文件操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IoOperation {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    // 除数为零
+    DivisionByZero,
+    // 数字无效：index 为在输入数组中的下标，value 为无法解析的原始文本
+    InvalidNumber {
+        index: Option<usize>,
+        value: Option<String>,
+    },
+    // 输入值或计算结果超出 f64 / 十进制的表示范围
+    OutOfRange {
+        source: ValueSource,
+    },
+    // 计算结果没有定义（NaN），例如 sqrt(-1)
+    UndefinedResult,
+    UnsupportedOperation {
+        operation: String,
+    },
+    // 没有可用的数据；skipped 为按 Skip 策略跳过的 NaN / 无穷大个数
+    EmptyInput {
+        skipped: usize,
+    },
+    // 参数取值不合法：expected 为对取值的要求，例如 "0..=100"、"> 0"、"encodingLabel"
+    // expected 是稳定的机器可读标记，不随语言变化；消息目录中有 "expected.<标记>" 时，
+    // 本地化消息中用这条说明代替标记
+    InvalidArgument {
+        name: String,
+        value: String,
+        expected: String,
+    },
+    InsufficientData {
+        required: usize,
+        actual: usize,
+    },
+    LengthMismatch {
+        x: usize,
+        y: usize,
+    },
+    // 最小二乘矩阵奇异，无法拟合
+    FitFailed,
+    // ===== 表达式语法错误 =====
+    EmptyExpression,
+    UnexpectedCharacter {
+        character: char,
+    },
+    UnexpectedToken {
+        token: String,
+    },
+    UnexpectedEnd,
+    UnclosedParenthesis,
+    UnknownFunction {
+        name: String,
+    },
+    UnknownConstant {
+        name: String,
+    },
+    WrongArgumentCount {
+        function: String,
+        expected: String,
+        actual: usize,
+    },
+    // 给内部错误附加在表达式中的位置（列号从 1 开始，按字符计），错误码沿用内部错误
+    At {
+        column: usize,
+        length: usize,
+        error: Box<CommandError>,
+    },
+    // ===== 菜单快捷键 =====
+    InvalidShortcut {
+        accelerator: String,
+        reason: String,
+    },
+    // 快捷键已被菜单项 id 使用
+    ShortcutConflict {
+        accelerator: String,
+        id: String,
+    },
+    // 菜单项被禁用，不能通过命令面板执行
+    ActionDisabled {
+        id: String,
+    },
+    // ===== 剪贴板 =====
+    // 系统剪贴板读写失败（例如被其他程序占用）
+    Clipboard {
+        reason: String,
+    },
+    // ===== 文档 =====
+    // 关闭有未保存修改的文档；path 为文档对应的文件，新建的文档为 None
+    UnsavedChanges {
+        id: String,
+        path: Option<String>,
+    },
+    // 保存新建的文档时没有指定保存路径
+    PathRequired,
+    // 文档中有 count 个字符无法用 encoding 保存，characters 为其中不重复的前几个
+    Unrepresentable {
+        encoding: String,
+        count: usize,
+        characters: String,
+    },
+    // ===== 资源与文件 =====
+    // 保存时目标目录只读或没有写入权限
+    ReadOnlyDirectory {
+        path: String,
+    },
+    NotFound {
+        resource: Resource,
+        id: String,
+    },
+    Io {
+        operation: IoOperation,
+        path: String,
+        reason: String,
+    },
+    ParseFailed {
+        format: String,
+        reason: String,
+    },
+    Internal {
+        reason: String,
+    },
+}
+
+impl CommandError {
+    // 给错误附加位置信息
+    pub fn at(self, column: usize, length: usize) -> Self {
+        CommandError::At {
+            column,
+            length: length.max(1),
+            error: Box::new(self),
+        }
+    }
+
+    pub fn invalid_argument(
+        name: impl Into<String>,
+        value: impl ToString,
+        expected: impl Into<String>,
+    ) -> Self {
+        CommandError::InvalidArgument {
+            name: name.into(),
+            value: value.to_string(),
+            expected: expected.into(),
+        }
+    }
+
+    pub fn io(operation: IoOperation, path: &std::path::Path, error: std::io::Error) -> Self {
+        CommandError::Io {
+            operation,
+            path: path.display().to_string(),
+            reason: error.to_string(),
+        }
+    }
+
+    // 稳定的错误码，不随语言变化
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::DivisionByZero => "DivisionByZero",
+            CommandError::InvalidNumber { .. } => "InvalidNumber",
+            CommandError::OutOfRange { .. } => "OutOfRange",
+            CommandError::UndefinedResult => "UndefinedResult",
+            CommandError::UnsupportedOperation { .. } => "UnsupportedOperation",
+            CommandError::EmptyInput { .. } => "EmptyInput",
+            CommandError::InvalidArgument { .. } => "InvalidArgument",
+            CommandError::InsufficientData { .. } => "InsufficientData",
+            CommandError::LengthMismatch { .. } => "LengthMismatch",
+            CommandError::FitFailed => "FitFailed",
+            CommandError::EmptyExpression => "EmptyExpression",
+            CommandError::UnexpectedCharacter { .. } => "UnexpectedCharacter",
+            CommandError::UnexpectedToken { .. } => "UnexpectedToken",
+            CommandError::UnexpectedEnd => "UnexpectedEnd",
+            CommandError::UnclosedParenthesis => "UnclosedParenthesis",
+            CommandError::UnknownFunction { .. } => "UnknownFunction",
+            CommandError::UnknownConstant { .. } => "UnknownConstant",
+            CommandError::WrongArgumentCount { .. } => "WrongArgumentCount",
+            CommandError::At { error, .. } => error.code(),
+            CommandError::InvalidShortcut { .. } => "InvalidShortcut",
+            CommandError::ShortcutConflict { .. } => "ShortcutConflict",
+            CommandError::ActionDisabled { .. } => "ActionDisabled",
+            CommandError::Clipboard { .. } => "Clipboard",
+            CommandError::UnsavedChanges { .. } => "UnsavedChanges",
+            CommandError::PathRequired => "PathRequired",
+            CommandError::Unrepresentable { .. } => "Unrepresentable",
+            CommandError::ReadOnlyDirectory { .. } => "ReadOnlyDirectory",
+            CommandError::NotFound { .. } => "NotFound",
+            CommandError::Io { .. } => "Io",
+            CommandError::ParseFailed { .. } => "ParseFailed",
+            CommandError::Internal { .. } => "Internal",
+        }
+    }
+
+    // 结构化的错误详情，没有额外信息时为 null
+    pub fn details(&self) -> Value {
+        match self {
+            CommandError::InvalidNumber { index, value } => {
+                json!({ "index": index, "value": value })
+            }
+            CommandError::OutOfRange { source } => json!({ "source": source }),
+            CommandError::UnsupportedOperation { operation } => json!({ "operation": operation }),
+            CommandError::EmptyInput { skipped } => json!({ "skipped": skipped }),
+            CommandError::InvalidArgument {
+                name,
+                value,
+                expected,
+            } => json!({ "name": name, "value": value, "expected": expected }),
+            CommandError::InsufficientData { required, actual } => {
+                json!({ "required": required, "actual": actual })
+            }
+            CommandError::LengthMismatch { x, y } => json!({ "x": x, "y": y }),
+            CommandError::UnexpectedCharacter { character } => json!({ "character": character }),
+            CommandError::UnexpectedToken { token } => json!({ "token": token }),
+            CommandError::UnknownFunction { name } | CommandError::UnknownConstant { name } => {
+                json!({ "name": name })
+            }
+            CommandError::WrongArgumentCount {
+                function,
+                expected,
+                actual,
+            } => json!({ "function": function, "expected": expected, "actual": actual }),
+            CommandError::At {
+                column,
+                length,
+                error,
+            } => {
+                let mut details = match error.details() {
+                    Value::Object(map) => map,
+                    _ => serde_json::Map::new(),
+                };
+                details.insert("column".to_string(), json!(column));
+                details.insert("length".to_string(), json!(length));
+                Value::Object(details)
+            }
+            CommandError::InvalidShortcut {
+                accelerator,
+                reason,
+            } => json!({ "accelerator": accelerator, "reason": reason }),
+            CommandError::ShortcutConflict { accelerator, id } => {
+                json!({ "accelerator": accelerator, "id": id })
+            }
+            CommandError::ActionDisabled { id } => json!({ "id": id }),
+            CommandError::Clipboard { reason } => json!({ "reason": reason }),
+            CommandError::UnsavedChanges { id, path } => json!({ "id": id, "path": path }),
+            CommandError::Unrepresentable {
+                encoding,
+                count,
+                characters,
+            } => json!({ "encoding": encoding, "count": count, "characters": characters }),
+            CommandError::ReadOnlyDirectory { path } => json!({ "path": path }),
+            CommandError::NotFound { resource, id } => json!({ "resource": resource, "id": id }),
+            CommandError::Io {
+                operation,
+                path,
+                reason,
+            } => json!({ "operation": operation, "path": path, "reason": reason }),
+            CommandError::ParseFailed { format, reason } => {
+                json!({ "format": format, "reason": reason })
+            }
+            CommandError::Internal { reason } => json!({ "reason": reason }),
+            CommandError::DivisionByZero
+            | CommandError::UndefinedResult
+            | CommandError::FitFailed
+            | CommandError::EmptyExpression
+            | CommandError::UnexpectedEnd
+            | CommandError::UnclosedParenthesis
+            | CommandError::PathRequired => Value::Null,
+        }
+    }
+
+    // 消息目录中的键，同一个错误码可能按详情细分为几条消息
+    fn message_key(&self) -> &'static str {
+        match self {
+            CommandError::DivisionByZero => "error.divisionByZero",
+            CommandError::InvalidNumber { index: Some(_), .. } => "error.invalidNumber.index",
+            CommandError::InvalidNumber { value: Some(_), .. } => "error.invalidNumber.value",
+            CommandError::InvalidNumber { .. } => "error.invalidNumber",
+            CommandError::OutOfRange {
+                source: ValueSource::Input,
+            } => "error.outOfRange.input",
+            CommandError::OutOfRange {
+                source: ValueSource::Result,
+            } => "error.outOfRange.result",
+            CommandError::UndefinedResult => "error.undefinedResult",
+            CommandError::UnsupportedOperation { .. } => "error.unsupportedOperation",
+            CommandError::EmptyInput { skipped: 0 } => "error.emptyInput",
+            CommandError::EmptyInput { .. } => "error.emptyInput.skipped",
+            CommandError::InvalidArgument { .. } => "error.invalidArgument",
+            CommandError::InsufficientData { .. } => "error.insufficientData",
+            CommandError::LengthMismatch { .. } => "error.lengthMismatch",
+            CommandError::FitFailed => "error.fitFailed",
+            CommandError::EmptyExpression => "error.emptyExpression",
+            CommandError::UnexpectedCharacter { .. } => "error.unexpectedCharacter",
+            CommandError::UnexpectedToken { .. } => "error.unexpectedToken",
+            CommandError::UnexpectedEnd => "error.unexpectedEnd",
+            CommandError::UnclosedParenthesis => "error.unclosedParenthesis",
+            CommandError::UnknownFunction { .. } => "error.unknownFunction",
+            CommandError::UnknownConstant { .. } => "error.unknownConstant",
+            CommandError::WrongArgumentCount { .. } => "error.wrongArgumentCount",
+            CommandError::At { .. } => "error.at",
+            CommandError::InvalidShortcut { .. } => "error.invalidShortcut",
+            CommandError::ShortcutConflict { .. } => "error.shortcutConflict",
+            CommandError::ActionDisabled { .. } => "error.actionDisabled",
+            CommandError::Clipboard { .. } => "error.clipboard",
+            CommandError::UnsavedChanges { path: Some(_), .. } => "error.unsavedChanges.path",
+            CommandError::UnsavedChanges { .. } => "error.unsavedChanges",
+            CommandError::PathRequired => "error.pathRequired",
+            CommandError::Unrepresentable { .. } => "error.unrepresentable",
+            CommandError::ReadOnlyDirectory { .. } => "error.readOnlyDirectory",
+            CommandError::NotFound {
+                resource: Resource::HistoryEntry,
+                ..
+            } => "error.notFound.historyEntry",
+            CommandError::NotFound {
+                resource: Resource::StatsSession,
+                ..
+            } => "error.notFound.statsSession",
+            CommandError::NotFound {
+                resource: Resource::Column,
+                ..
+            } => "error.notFound.column",
+            CommandError::NotFound {
+                resource: Resource::MenuItem,
+                ..
+            } => "error.notFound.menuItem",
+            CommandError::NotFound {
+                resource: Resource::Action,
+                ..
+            } => "error.notFound.action",
+            CommandError::NotFound {
+                resource: Resource::ClipboardEntry,
+                ..
+            } => "error.notFound.clipboardEntry",
+            CommandError::NotFound {
+                resource: Resource::Document,
+                ..
+            } => "error.notFound.document",
+            CommandError::NotFound {
+                resource: Resource::RecoverySnapshot,
+                ..
+            } => "error.notFound.recoverySnapshot",
+            CommandError::Io {
+                operation: IoOperation::Read,
+                ..
+            } => "error.io.read",
+            CommandError::Io {
+                operation: IoOperation::Write,
+                ..
+            } => "error.io.write",
+            CommandError::ParseFailed { .. } => "error.parseFailed",
+            CommandError::Internal { .. } => "error.internal",
+        }
+    }
+
+    // 按指定语言生成提示文字：消息模板来自消息目录，占位符用 details 中的字段填充
+    pub fn message(&self, locale: Locale) -> String {
+        let mut args = match self.details() {
+            Value::Object(map) => map,
+            _ => serde_json::Map::new(),
+        };
+        match self {
+            // 面向用户的序号从 1 开始
+            CommandError::InvalidNumber {
+                index: Some(index), ..
+            } => {
+                args.insert("position".to_string(), json!(index + 1));
+            }
+            CommandError::ParseFailed { format, .. } => {
+                args.insert("format".to_string(), json!(format.to_uppercase()));
+            }
+            CommandError::InvalidArgument { expected, .. } => {
+                if let Some(text) = i18n::lookup(locale, &format!("expected.{}", expected)) {
+                    args.insert("expected".to_string(), json!(text));
+                }
+            }
+            CommandError::At { error, .. } => {
+                args.insert("message".to_string(), json!(error.message(locale)));
+            }
+            _ => {}
+        }
+        i18n::translate(locale, self.message_key(), &args)
+    }
+}
+
+// Display 使用当前语言，记录日志和计算历史时直接使用
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message(Locale::current()))
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+// 菜单、窗口等 tauri 接口的失败都是内部错误
+#[cfg(feature = "tauri")]
+impl From<tauri::Error> for CommandError {
+    fn from(error: tauri::Error) -> Self {
+        CommandError::Internal {
+            reason: error.to_string(),
+        }
+    }
+}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CommandError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
@@ -0,0 +1,605 @@
+// 表达式求值器：分词 -> 语法分析（递归下降）-> 求值
+// 支持 + - * / % ^、括号、一元负号，以及 sin、cos、ln、sqrt、abs 等常用函数
+use crate::error::{CommandError, ValueSource};
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    LParen,
+    RParen,
+    Comma,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+    length: usize,
+}
+
+// 分词：把输入字符串切分成记号，同时记录每个记号所在的列
+fn tokenize(input: &str) -> Result<Vec<Token>, CommandError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        // 数字：支持小数和科学计数法（如 1.5e-3）
+        if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse::<f64>().map_err(|_| {
+                CommandError::InvalidNumber {
+                    index: None,
+                    value: Some(text.clone()),
+                }
+                .at(column, i - start)
+            })?;
+            // 超出 f64 范围的字面量（如 1e400）解析为无穷大，与计算结果一样拒绝
+            if !value.is_finite() {
+                return Err(CommandError::OutOfRange {
+                    source: ValueSource::Input,
+                }
+                .at(column, i - start));
+            }
+            tokens.push(Token {
+                kind: TokenKind::Number(value),
+                column,
+                length: i - start,
+            });
+            continue;
+        }
+
+        // 标识符：函数名或常量名
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            tokens.push(Token {
+                kind: TokenKind::Ident(name.to_lowercase()),
+                column,
+                length: i - start,
+            });
+            continue;
+        }
+
+        let kind = match c {
+            '+' => TokenKind::Plus,
+            '-' | '−' => TokenKind::Minus,
+            '*' | '×' => TokenKind::Star,
+            '/' | '÷' => TokenKind::Slash,
+            '%' => TokenKind::Percent,
+            '^' => TokenKind::Caret,
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            ',' => TokenKind::Comma,
+            _ => return Err(CommandError::UnexpectedCharacter { character: c }.at(column, 1)),
+        };
+        tokens.push(Token {
+            kind,
+            column,
+            length: 1,
+        });
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Power,
+}
+
+// 语法树：每个节点都保留列信息，求值出错时可以定位到具体的运算符或函数
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    Constant(f64),
+    Negate(Box<Expr>),
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+        column: usize,
+    },
+    Call {
+        name: String,
+        args: Vec<Expr>,
+        column: usize,
+        length: usize,
+    },
+}
+
+// 递归下降语法分析器，优先级从低到高：
+//   expr    := term (('+' | '-') term)*
+//   term    := unary (('*' | '/' | '%') unary)*
+//   unary   := ('-' | '+') unary | power
+//   power   := primary ('^' unary)?        （右结合，-2^2 = -4）
+//   primary := number | ident | ident '(' args ')' | '(' expr ')'
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end_column: usize,
+    // 当前的嵌套层数，见 enter
+    depth: usize,
+}
+
+// 语法树的最大深度。分析和求值都是递归的，过深的嵌套会耗尽栈空间（异步命令的线程栈只有 2 MB）；
+// 括号、一元运算符、乘方的指数、函数参数，以及连续的二元运算（左结合，每个运算符多一层）都算一层
+const MAX_DEPTH: usize = 100;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn unexpected_end(&self) -> CommandError {
+        CommandError::UnexpectedEnd.at(self.end_column, 1)
+    }
+
+    // 进入一层嵌套，超过 MAX_DEPTH 时报错；成功时调用方负责在这一层结束后减回 depth
+    fn enter(&mut self, column: usize) -> Result<(), CommandError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(CommandError::invalid_argument(
+                "expression",
+                self.depth,
+                format!("nesting depth <= {}", MAX_DEPTH),
+            )
+            .at(column, 1));
+        }
+        Ok(())
+    }
+
+    fn unexpected(token: &Token) -> CommandError {
+        CommandError::UnexpectedToken {
+            token: describe(&token.kind),
+        }
+        .at(token.column, token.length)
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, CommandError> {
+        let depth = self.depth;
+        let mut left = self.parse_term()?;
+        while let Some(token) = self.peek() {
+            let op = match token.kind {
+                TokenKind::Plus => BinaryOp::Add,
+                TokenKind::Minus => BinaryOp::Subtract,
+                _ => break,
+            };
+            let column = token.column;
+            self.pos += 1;
+            self.enter(column)?;
+            let right = self.parse_term()?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+                column,
+            };
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, CommandError> {
+        let depth = self.depth;
+        let mut left = self.parse_unary()?;
+        while let Some(token) = self.peek() {
+            let op = match token.kind {
+                TokenKind::Star => BinaryOp::Multiply,
+                TokenKind::Slash => BinaryOp::Divide,
+                TokenKind::Percent => BinaryOp::Modulo,
+                _ => break,
+            };
+            let column = token.column;
+            self.pos += 1;
+            self.enter(column)?;
+            let right = self.parse_unary()?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+                column,
+            };
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, CommandError> {
+        let Some(token) = self.peek() else {
+            return self.parse_power();
+        };
+        let negate = match token.kind {
+            TokenKind::Minus => true,
+            TokenKind::Plus => false,
+            _ => return self.parse_power(),
+        };
+        let column = token.column;
+        self.pos += 1;
+        self.enter(column)?;
+        let inner = self.parse_unary()?;
+        self.depth -= 1;
+        Ok(if negate {
+            Expr::Negate(Box::new(inner))
+        } else {
+            inner
+        })
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, CommandError> {
+        let base = self.parse_primary()?;
+        if let Some(token) = self.peek() {
+            if token.kind == TokenKind::Caret {
+                let column = token.column;
+                self.pos += 1;
+                self.enter(column)?;
+                let exponent = self.parse_unary()?;
+                self.depth -= 1;
+                return Ok(Expr::Binary {
+                    op: BinaryOp::Power,
+                    left: Box::new(base),
+                    right: Box::new(exponent),
+                    column,
+                });
+            }
+        }
+        Ok(base)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, CommandError> {
+        let token = self.next().ok_or_else(|| self.unexpected_end())?;
+        match token.kind {
+            TokenKind::Number(value) => Ok(Expr::Number(value)),
+            TokenKind::LParen => {
+                self.enter(token.column)?;
+                let inner = self.parse_expr()?;
+                self.depth -= 1;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => Ok(inner),
+                    Some(other) => Err(Self::unexpected(&other)),
+                    None => Err(CommandError::UnclosedParenthesis.at(token.column, 1)),
+                }
+            }
+            TokenKind::Ident(ref name) => {
+                let is_call = matches!(self.peek().map(|t| &t.kind), Some(TokenKind::LParen));
+                if !is_call {
+                    return constant(name).map(Expr::Constant).ok_or_else(|| {
+                        CommandError::UnknownConstant { name: name.clone() }
+                            .at(token.column, token.length)
+                    });
+                }
+                self.pos += 1;
+                self.enter(token.column)?;
+                let mut args = Vec::new();
+                if matches!(self.peek().map(|t| &t.kind), Some(TokenKind::RParen)) {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        args.push(self.parse_expr()?);
+                        match self.next() {
+                            Some(Token {
+                                kind: TokenKind::Comma,
+                                ..
+                            }) => continue,
+                            Some(Token {
+                                kind: TokenKind::RParen,
+                                ..
+                            }) => break,
+                            Some(other) => return Err(Self::unexpected(&other)),
+                            None => return Err(self.unexpected_end()),
+                        }
+                    }
+                }
+                self.depth -= 1;
+                Ok(Expr::Call {
+                    name: name.clone(),
+                    args,
+                    column: token.column,
+                    length: token.length,
+                })
+            }
+            _ => Err(Self::unexpected(&token)),
+        }
+    }
+}
+
+// 记号的原文，放进 UnexpectedToken 错误中，与界面语言无关
+fn describe(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::Number(value) => format!("'{}'", value),
+        TokenKind::Ident(name) => format!("'{}'", name),
+        TokenKind::Plus => "'+'".to_string(),
+        TokenKind::Minus => "'-'".to_string(),
+        TokenKind::Star => "'*'".to_string(),
+        TokenKind::Slash => "'/'".to_string(),
+        TokenKind::Percent => "'%'".to_string(),
+        TokenKind::Caret => "'^'".to_string(),
+        TokenKind::LParen => "'('".to_string(),
+        TokenKind::RParen => "')'".to_string(),
+        TokenKind::Comma => "','".to_string(),
+    }
+}
+
+fn constant(name: &str) -> Option<f64> {
+    match name {
+        "pi" | "π" => Some(std::f64::consts::PI),
+        "e" => Some(std::f64::consts::E),
+        "tau" => Some(std::f64::consts::TAU),
+        _ => None,
+    }
+}
+
+// 检查中间结果：和 calculate 一样拒绝 NaN 与无穷大
+fn check_finite(value: f64, column: usize, length: usize) -> Result<f64, CommandError> {
+    if value.is_nan() {
+        Err(CommandError::UndefinedResult.at(column, length))
+    } else if value.is_infinite() {
+        Err(CommandError::OutOfRange {
+            source: ValueSource::Result,
+        }
+        .at(column, length))
+    } else {
+        Ok(value)
+    }
+}
+
+fn eval(expr: &Expr) -> Result<f64, CommandError> {
+    match expr {
+        Expr::Number(value) | Expr::Constant(value) => Ok(*value),
+        Expr::Negate(inner) => Ok(-eval(inner)?),
+        Expr::Binary {
+            op,
+            left,
+            right,
+            column,
+        } => {
+            let a = eval(left)?;
+            let b = eval(right)?;
+            let value = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Subtract => a - b,
+                BinaryOp::Multiply => a * b,
+                BinaryOp::Divide | BinaryOp::Modulo => {
+                    // 处理除零错误
+                    if b == 0.0 {
+                        return Err(CommandError::DivisionByZero.at(*column, 1));
+                    }
+                    if *op == BinaryOp::Divide {
+                        a / b
+                    } else {
+                        a % b
+                    }
+                }
+                BinaryOp::Power => a.powf(b),
+            };
+            check_finite(value, *column, 1)
+        }
+        Expr::Call {
+            name,
+            args,
+            column,
+            length,
+        } => {
+            let values = args.iter().map(eval).collect::<Result<Vec<_>, _>>()?;
+            let value = call_function(name, &values, *column, *length)?;
+            check_finite(value, *column, *length)
+        }
+    }
+}
+
+fn call_function(
+    name: &str,
+    args: &[f64],
+    column: usize,
+    length: usize,
+) -> Result<f64, CommandError> {
+    let arity_error = |expected: &str| {
+        CommandError::WrongArgumentCount {
+            function: name.to_string(),
+            expected: expected.to_string(),
+            actual: args.len(),
+        }
+        .at(column, length)
+    };
+
+    // 可变参数函数
+    match name {
+        "min" | "max" => {
+            if args.is_empty() {
+                return Err(arity_error(">= 1"));
+            }
+            let fold: fn(f64, f64) -> f64 = if name == "min" { f64::min } else { f64::max };
+            return Ok(args[1..].iter().cloned().fold(args[0], fold));
+        }
+        "log" if args.len() == 2 => return Ok(args[0].ln() / args[1].ln()),
+        "pow" | "atan2" | "hypot" => {
+            if args.len() != 2 {
+                return Err(arity_error("2"));
+            }
+            return Ok(match name {
+                "pow" => args[0].powf(args[1]),
+                "atan2" => args[0].atan2(args[1]),
+                _ => args[0].hypot(args[1]),
+            });
+        }
+        _ => {}
+    }
+
+    let unary: fn(f64) -> f64 = match name {
+        "sin" => f64::sin,
+        "cos" => f64::cos,
+        "tan" => f64::tan,
+        "asin" => f64::asin,
+        "acos" => f64::acos,
+        "atan" => f64::atan,
+        "sinh" => f64::sinh,
+        "cosh" => f64::cosh,
+        "tanh" => f64::tanh,
+        "sqrt" => f64::sqrt,
+        "cbrt" => f64::cbrt,
+        "abs" => f64::abs,
+        "ln" => f64::ln,
+        "log" | "log10" => f64::log10,
+        "log2" => f64::log2,
+        "exp" => f64::exp,
+        "floor" => f64::floor,
+        "ceil" => f64::ceil,
+        "round" => f64::round,
+        "trunc" => f64::trunc,
+        "sign" => f64::signum,
+        _ => {
+            return Err(CommandError::UnknownFunction {
+                name: name.to_string(),
+            }
+            .at(column, length))
+        }
+    };
+    if args.len() != 1 {
+        return Err(arity_error("1"));
+    }
+    Ok(unary(args[0]))
+}
+
+// 对外入口：解析并计算整个表达式
+// 出错时返回 CommandError::At，column 从 1 开始计数（按字符），length 为出错记号的长度，
+// 前端可以据此在输入框中给出错的记号画下划线
+pub fn evaluate(input: &str) -> Result<f64, CommandError> {
+    let tokens = tokenize(input)?;
+    let end_column = input.chars().count() + 1;
+    if tokens.is_empty() {
+        return Err(CommandError::EmptyExpression.at(1, 1));
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end_column,
+        depth: 0,
+    };
+    let expr = parser.parse_expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(Parser::unexpected(token));
+    }
+    eval(&expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 取出 At 包裹的错误和出错的列号
+    fn error_at(input: &str) -> (usize, CommandError) {
+        match evaluate(input) {
+            Err(CommandError::At { column, error, .. }) => (column, *error),
+            other => panic!("{} 应该出错，实际为 {:?}", input, other),
+        }
+    }
+
+    fn is_too_deep(error: &CommandError) -> bool {
+        matches!(error, CommandError::InvalidArgument { name, .. } if name == "expression")
+    }
+
+    #[test]
+    fn follows_precedence_and_associativity() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(evaluate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(evaluate("-2^2").unwrap(), -4.0);
+        assert_eq!(evaluate("2^3^2").unwrap(), 512.0);
+        assert_eq!(evaluate("10 - 4 - 3").unwrap(), 3.0);
+        assert_eq!(evaluate("max(1, 5, 3) + sqrt(16)").unwrap(), 9.0);
+    }
+
+    #[test]
+    fn reports_error_columns() {
+        let (column, error) = error_at("1 + 2 / 0");
+        assert_eq!(column, 7);
+        assert!(matches!(error, CommandError::DivisionByZero));
+
+        let (column, error) = error_at("(1 + 2");
+        assert_eq!(column, 1);
+        assert!(matches!(error, CommandError::UnclosedParenthesis));
+    }
+
+    #[test]
+    fn accepts_nesting_up_to_the_limit() {
+        let input = format!("{}1{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(evaluate(&input).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn rejects_deep_parentheses() {
+        let input = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        let (column, error) = error_at(&input);
+        assert_eq!(column, MAX_DEPTH + 1);
+        assert!(is_too_deep(&error));
+    }
+
+    #[test]
+    fn rejects_deep_unary_chains() {
+        let (_, error) = error_at(&format!("{}1", "-".repeat(100_000)));
+        assert!(is_too_deep(&error));
+        let (_, error) = error_at(&format!("{}1", "+".repeat(100_000)));
+        assert!(is_too_deep(&error));
+        let (_, error) = error_at(&format!("2{}", "^2".repeat(100_000)));
+        assert!(is_too_deep(&error));
+    }
+
+    #[test]
+    fn rejects_long_operator_chains() {
+        let (_, error) = error_at(&vec!["1"; 100_000].join("+"));
+        assert!(is_too_deep(&error));
+        assert_eq!(
+            evaluate(&vec!["1"; MAX_DEPTH].join("+")).unwrap(),
+            MAX_DEPTH as f64
+        );
+    }
+}
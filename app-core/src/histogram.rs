@@ -0,0 +1,340 @@
+// 直方图：计算分箱边界和每个箱的计数，前端只负责画图
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommandError;
+use crate::statistics::{self, NonFinitePolicy};
+
+// 箱数上限，防止宽度过小时生成过多的箱
+const MAX_BINS: usize = 10_000;
+
+// 分箱规则
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "camelCase")]
+pub enum BinRule {
+    // 固定箱数
+    Count { count: usize },
+    // 固定箱宽
+    Width { width: f64 },
+    // Sturges 规则：箱数 = ceil(log2(n)) + 1（默认）
+    #[default]
+    Sturges,
+    // Scott 规则：箱宽 = 3.49 * 标准差 * n^(-1/3)
+    Scott,
+    // Freedman–Diaconis 规则：箱宽 = 2 * IQR * n^(-1/3)，对离群值更稳健
+    FreedmanDiaconis,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramOptions {
+    #[serde(default)]
+    pub bins: BinRule,
+    // 统计范围 [min, max]，省略时使用数据本身的最小值和最大值；范围外的数据不计入任何箱
+    pub range: Option<(f64, f64)>,
+    // 累积计数
+    #[serde(default)]
+    pub cumulative: bool,
+    // 密度归一化：各箱面积之和为 1；与 cumulative 同时使用时最后一个值为 1
+    #[serde(default)]
+    pub density: bool,
+    #[serde(default)]
+    pub non_finite: NonFinitePolicy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Histogram {
+    // 箱边界，长度为箱数 + 1；每个箱为左闭右开区间，最后一个箱为闭区间
+    pub edges: Vec<f64>,
+    // 每个箱的原始计数
+    pub counts: Vec<u64>,
+    // 按 cumulative / density 处理后用于绘图的值
+    pub values: Vec<f64>,
+    pub bin_width: f64,
+    // 落在范围内的数据个数
+    pub total: u64,
+    // 落在范围外的数据个数
+    pub outside: u64,
+    // 按 Skip 策略被跳过的 NaN / 无穷大个数
+    pub skipped: usize,
+}
+
+// 根据规则计算箱数
+fn bin_count(
+    rule: &BinRule,
+    sorted: &[f64],
+    low: f64,
+    high: f64,
+) -> Result<usize, CommandError> {
+    let n = sorted.len() as f64;
+    let span = high - low;
+    let sturges = (n.log2().ceil() as usize + 1).max(1);
+
+    let from_width = |width: f64| -> Result<usize, CommandError> {
+        if !(width > 0.0 && width.is_finite()) {
+            return Err(CommandError::invalid_argument("bins.width", width, "> 0"));
+        }
+        Ok(((span / width).ceil() as usize).max(1))
+    };
+
+    let count = match rule {
+        BinRule::Count { count } => {
+            if *count == 0 {
+                return Err(CommandError::invalid_argument("bins.count", count, "> 0"));
+            }
+            *count
+        }
+        BinRule::Width { width } => from_width(*width)?,
+        BinRule::Sturges => sturges,
+        BinRule::Scott | BinRule::FreedmanDiaconis => {
+            let spread = if matches!(rule, BinRule::Scott) {
+                let mean = sorted.iter().sum::<f64>() / n;
+                let variance = sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+                3.49 * variance.sqrt()
+            } else {
+                let iqr = statistics::percentile_sorted(sorted, 75.0)
+                    - statistics::percentile_sorted(sorted, 25.0);
+                2.0 * iqr
+            };
+            let width = spread * n.powf(-1.0 / 3.0);
+            // 数据过于集中（标准差或 IQR 为 0）时退回 Sturges 规则
+            if width > 0.0 {
+                from_width(width)?
+            } else {
+                sturges
+            }
+        }
+    };
+
+    if count > MAX_BINS {
+        return Err(CommandError::invalid_argument(
+            "bins",
+            count,
+            format!("<= {}", MAX_BINS),
+        ));
+    }
+    Ok(count)
+}
+
+pub fn compute(numbers: &[f64], options: &HistogramOptions) -> Result<Histogram, CommandError> {
+    if numbers.is_empty() {
+        return Err(CommandError::EmptyInput { skipped: 0 });
+    }
+    // 直方图无法容纳 NaN / 无穷大，Propagate 策略按 Reject 处理
+    let policy = match options.non_finite {
+        NonFinitePolicy::Skip => NonFinitePolicy::Skip,
+        _ => NonFinitePolicy::Reject,
+    };
+    let (mut values, skipped) = statistics::prepare(numbers, policy)?;
+    if values.is_empty() {
+        return Err(CommandError::EmptyInput { skipped });
+    }
+    values.sort_by(f64::total_cmp);
+
+    let (mut low, mut high) = match options.range {
+        Some((low, high)) => {
+            if !(low.is_finite() && high.is_finite() && low <= high) {
+                return Err(CommandError::invalid_argument(
+                    "range",
+                    format!("[{}, {}]", low, high),
+                    "min <= max",
+                ));
+            }
+            (low, high)
+        }
+        None => (values[0], values[values.len() - 1]),
+    };
+    // 所有数据都相同时，把范围向两边各扩展 0.5，避免箱宽为 0
+    if low == high {
+        low -= 0.5;
+        high += 0.5;
+    }
+
+    let count = bin_count(&options.bins, &values, low, high)?;
+    // 固定箱宽时最后一个边界可能超过 high
+    let bin_width = match options.bins {
+        BinRule::Width { width } => width,
+        _ => (high - low) / count as f64,
+    };
+    let mut edges: Vec<f64> = (0..=count).map(|i| low + bin_width * i as f64).collect();
+    if !matches!(options.bins, BinRule::Width { .. }) {
+        // 消除浮点累积误差，保证最大值一定落在最后一个箱内
+        edges[count] = high;
+    }
+    let upper = edges[count];
+
+    let mut counts = vec![0u64; count];
+    let mut outside = 0;
+    for &x in &values {
+        if x < low || x > upper {
+            outside += 1;
+            continue;
+        }
+        let index = (((x - low) / bin_width).floor() as usize).min(count - 1);
+        counts[index] += 1;
+    }
+    let total: u64 = counts.iter().sum();
+
+    let mut plotted: Vec<f64> = counts.iter().map(|&c| c as f64).collect();
+    if options.cumulative {
+        let mut running = 0.0;
+        for value in &mut plotted {
+            running += *value;
+            *value = running;
+        }
+    }
+    if options.density && total > 0 {
+        let scale = if options.cumulative {
+            total as f64
+        } else {
+            total as f64 * bin_width
+        };
+        for value in &mut plotted {
+            *value /= scale;
+        }
+    }
+
+    Ok(Histogram {
+        edges,
+        counts,
+        values: plotted,
+        bin_width,
+        total,
+        outside,
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_bins(bins: BinRule) -> HistogramOptions {
+        HistogramOptions {
+            bins,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bins_are_left_closed_and_the_last_is_closed() {
+        let numbers = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let histogram = compute(&numbers, &with_bins(BinRule::Count { count: 4 })).unwrap();
+        assert_eq!(histogram.edges, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(histogram.counts, vec![1, 1, 1, 2]);
+        assert_eq!((histogram.total, histogram.outside), (5, 0));
+    }
+
+    #[test]
+    fn fixed_width_may_extend_past_the_maximum() {
+        let numbers = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let histogram = compute(&numbers, &with_bins(BinRule::Width { width: 1.5 })).unwrap();
+        assert_eq!(histogram.edges, vec![0.0, 1.5, 3.0, 4.5]);
+        assert_eq!(histogram.counts, vec![2, 1, 2]);
+        assert_eq!(histogram.bin_width, 1.5);
+    }
+
+    #[test]
+    fn automatic_rules_choose_bin_counts() {
+        let numbers: Vec<f64> = (0..8).map(f64::from).collect();
+        // Sturges：ceil(log2(8)) + 1 = 4
+        let histogram = compute(&numbers, &HistogramOptions::default()).unwrap();
+        assert_eq!(histogram.counts.len(), 4);
+
+        // IQR 为 0 时 Freedman–Diaconis 退回 Sturges
+        let mut clustered = vec![5.0; 8];
+        clustered.push(9.0);
+        let histogram = compute(&clustered, &with_bins(BinRule::FreedmanDiaconis)).unwrap();
+        assert_eq!(histogram.counts.len(), 5);
+
+        let histogram = compute(&numbers, &with_bins(BinRule::Scott)).unwrap();
+        assert_eq!(histogram.counts.iter().sum::<u64>(), 8);
+    }
+
+    #[test]
+    fn constant_data_gets_a_unit_range() {
+        let histogram = compute(&[2.0; 3], &with_bins(BinRule::Count { count: 2 })).unwrap();
+        assert_eq!(histogram.edges, vec![1.5, 2.0, 2.5]);
+        assert_eq!(histogram.counts, vec![0, 3]);
+    }
+
+    #[test]
+    fn range_excludes_outside_values() {
+        let options = HistogramOptions {
+            bins: BinRule::Count { count: 2 },
+            range: Some((1.0, 3.0)),
+            ..Default::default()
+        };
+        let histogram = compute(&[0.0, 1.0, 2.0, 3.0, 4.0], &options).unwrap();
+        assert_eq!(histogram.counts, vec![1, 2]);
+        assert_eq!((histogram.total, histogram.outside), (3, 2));
+    }
+
+    #[test]
+    fn density_and_cumulative_values() {
+        let numbers = [0.0, 0.5, 1.0, 3.0];
+        let options = HistogramOptions {
+            bins: BinRule::Count { count: 2 },
+            density: true,
+            ..Default::default()
+        };
+        let histogram = compute(&numbers, &options).unwrap();
+        let area: f64 = histogram.values.iter().sum::<f64>() * histogram.bin_width;
+        assert!((area - 1.0).abs() < 1e-12);
+
+        let options = HistogramOptions {
+            cumulative: true,
+            ..options
+        };
+        let histogram = compute(&numbers, &options).unwrap();
+        assert_eq!(histogram.values, vec![0.75, 1.0]);
+    }
+
+    #[test]
+    fn validates_input_and_rules() {
+        assert!(matches!(
+            compute(&[], &HistogramOptions::default()),
+            Err(CommandError::EmptyInput { skipped: 0 })
+        ));
+        for bins in [
+            BinRule::Count { count: 0 },
+            BinRule::Width { width: 0.0 },
+            BinRule::Width { width: f64::NAN },
+            BinRule::Width { width: 1e-9 },
+        ] {
+            assert!(matches!(
+                compute(&[0.0, 1.0], &with_bins(bins)),
+                Err(CommandError::InvalidArgument { .. })
+            ));
+        }
+        let options = HistogramOptions {
+            range: Some((2.0, 1.0)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            compute(&[1.0], &options),
+            Err(CommandError::InvalidArgument { name, .. }) if name == "range"
+        ));
+    }
+
+    #[test]
+    fn non_finite_values_are_skipped_or_rejected() {
+        let numbers = [1.0, f64::NAN, 2.0];
+        let options = HistogramOptions {
+            non_finite: NonFinitePolicy::Skip,
+            ..Default::default()
+        };
+        let histogram = compute(&numbers, &options).unwrap();
+        assert_eq!((histogram.total, histogram.skipped), (2, 1));
+
+        // Propagate 按 Reject 处理
+        let options = HistogramOptions {
+            non_finite: NonFinitePolicy::Propagate,
+            ..Default::default()
+        };
+        assert!(matches!(
+            compute(&numbers, &options),
+            Err(CommandError::InvalidNumber { index: Some(1), .. })
+        ));
+    }
+}
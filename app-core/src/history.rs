@@ -0,0 +1,301 @@
+// 计算历史：记录每一次计算器命令的输入、结果（或错误）和时间戳
+// 以 JSON 文件保存在应用数据目录下
+// 每次记录都会重写整个文件，所以写成紧凑的 JSON，并且不保存过长的输入
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::atomic_write;
+use crate::decimal::DecimalOptions;
+use crate::error::{CommandError, IoOperation};
+use crate::statistics::StatisticsOptions;
+
+// 最多保留的历史条数，超出后丢弃最旧的记录
+const MAX_ENTRIES: usize = 1000;
+// 批量统计最多保存的输入个数，超出的部分只记录个数
+const MAX_STORED_NUMBERS: usize = 200;
+
+// 被记录的操作及其输入参数，重放时按原样再执行一次
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Operation {
+    Calculate {
+        operation: String,
+        a: f64,
+        b: f64,
+    },
+    SafeDivide {
+        a: f64,
+        b: f64,
+    },
+    ProcessNumbers {
+        numbers: Vec<f64>,
+        #[serde(default)]
+        options: StatisticsOptions,
+        // 超出 MAX_STORED_NUMBERS 而没有保存的输入个数，不为 0 时无法按原样重放
+        #[serde(default)]
+        omitted: usize,
+    },
+    EvaluateExpression {
+        expression: String,
+    },
+    CalculateDecimal {
+        operation: String,
+        a: String,
+        b: String,
+        #[serde(default)]
+        options: DecimalOptions,
+    },
+}
+
+impl Operation {
+    // 批量统计：只保存前 MAX_STORED_NUMBERS 个输入
+    pub fn process_numbers(mut numbers: Vec<f64>, options: StatisticsOptions) -> Self {
+        let omitted = numbers.len().saturating_sub(MAX_STORED_NUMBERS);
+        numbers.truncate(MAX_STORED_NUMBERS);
+        Operation::ProcessNumbers {
+            numbers,
+            options,
+            omitted,
+        }
+    }
+
+    // 是否保存了全部输入（可以重放）
+    pub fn is_complete(&self) -> bool {
+        !matches!(self, Operation::ProcessNumbers { omitted, .. } if *omitted > 0)
+    }
+}
+
+// 一条历史记录：result 和 error 二者只会有一个
+// error 保存序列化后的 CommandError（{ code, message, details }），旧版本的历史中为纯文本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub timestamp: u64,
+    pub operation: Operation,
+    pub result: Option<Value>,
+    pub error: Option<Value>,
+}
+
+impl HistoryEntry {
+    // 错误提示文字，兼容新旧两种格式
+    fn error_message(&self) -> Option<String> {
+        self.error.as_ref().map(|error| match error {
+            Value::String(text) => text.clone(),
+            other => other["message"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryData {
+    next_id: u64,
+    entries: Vec<HistoryEntry>,
+    // 每次修改加一，写文件时用来判断手里的快照是否已经过时；不保存到文件
+    #[serde(skip)]
+    revision: u64,
+}
+
+pub struct HistoryStore {
+    file: PathBuf,
+    data: Mutex<HistoryData>,
+    // 已经写入文件的最新版本号，写文件期间持有，保证旧快照不会覆盖新快照
+    written: Mutex<u64>,
+}
+
+impl HistoryStore {
+    // 从文件加载历史；文件不存在或内容损坏时从空历史开始
+    pub fn load(file: PathBuf) -> Self {
+        let data = fs::read_to_string(&file)
+            .ok()
+            .and_then(|text| match serde_json::from_str::<HistoryData>(&text) {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    eprintln!("[RUST] 历史记录文件解析失败，将重新开始记录: {:?}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        HistoryStore {
+            file,
+            data: Mutex::new(data),
+            written: Mutex::new(0),
+        }
+    }
+
+    // 把当前历史原子地写回磁盘（见 atomic_write.rs）；写入失败只打印日志，不影响计算命令本身
+    // 持有 data 时只做序列化，释放 data 之后再写文件，所以写文件期间的查询和记录不会被阻塞
+    fn persist(&self, mut data: MutexGuard<'_, HistoryData>) {
+        data.revision += 1;
+        let revision = data.revision;
+        let text = serde_json::to_string(&*data);
+        drop(data);
+        let text = match text {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("[RUST] 序列化历史记录失败: {:?}", e);
+                return;
+            }
+        };
+
+        let mut written = self.written.lock().unwrap();
+        // 并发修改时，更新的快照可能已经先写入了
+        if *written >= revision {
+            return;
+        }
+        if let Some(dir) = self.file.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                eprintln!("[RUST] 创建历史记录目录失败: {:?}", e);
+                return;
+            }
+        }
+        match atomic_write::write_file(&self.file, text.as_bytes(), 0) {
+            Ok(()) => *written = revision,
+            Err(e) => eprintln!("[RUST] 保存历史记录失败: {}", e),
+        }
+    }
+
+    // 记录一次操作的结果，返回新生成的历史条目
+    pub fn record<T: Serialize>(
+        &self,
+        operation: Operation,
+        outcome: &Result<T, CommandError>,
+    ) -> HistoryEntry {
+        let (result, error) = match outcome {
+            Ok(value) => (serde_json::to_value(value).ok(), None),
+            Err(e) => (None, serde_json::to_value(e).ok()),
+        };
+
+        let mut data = self.data.lock().unwrap();
+        data.next_id += 1;
+        let entry = HistoryEntry {
+            id: data.next_id,
+            timestamp: crate::current_timestamp(),
+            operation,
+            result,
+            error,
+        };
+        data.entries.push(entry.clone());
+        if data.entries.len() > MAX_ENTRIES {
+            let overflow = data.entries.len() - MAX_ENTRIES;
+            data.entries.drain(..overflow);
+        }
+        self.persist(data);
+        entry
+    }
+
+    // 按时间倒序列出历史，limit 为空时返回全部
+    pub fn list(&self, limit: Option<usize>) -> Vec<HistoryEntry> {
+        let data = self.data.lock().unwrap();
+        data.entries
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    // 在操作类型、输入、结果和错误信息中做不区分大小写的关键字搜索
+    pub fn search(&self, query: &str, limit: Option<usize>) -> Vec<HistoryEntry> {
+        let query = query.trim().to_lowercase();
+        let data = self.data.lock().unwrap();
+        data.entries
+            .iter()
+            .rev()
+            .filter(|entry| {
+                query.is_empty()
+                    || serde_json::to_string(entry)
+                        .map(|text| text.to_lowercase().contains(&query))
+                        .unwrap_or(false)
+            })
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<HistoryEntry> {
+        let data = self.data.lock().unwrap();
+        data.entries.iter().find(|entry| entry.id == id).cloned()
+    }
+
+    // 删除指定的历史条目，返回是否真的删除了
+    pub fn delete(&self, id: u64) -> bool {
+        let mut data = self.data.lock().unwrap();
+        let before = data.entries.len();
+        data.entries.retain(|entry| entry.id != id);
+        let removed = data.entries.len() != before;
+        if removed {
+            self.persist(data);
+        }
+        removed
+    }
+
+    pub fn clear(&self) {
+        let mut data = self.data.lock().unwrap();
+        data.entries.clear();
+        self.persist(data);
+    }
+
+    // 导出全部历史到指定文件，支持 json 和 csv 两种格式，返回导出的条数
+    // 先复制一份条目再释放锁，生成内容和写文件时不阻塞记录和查询
+    pub fn export(&self, path: &Path, format: &str) -> Result<usize, CommandError> {
+        let entries = self.data.lock().unwrap().entries.clone();
+        let content = match format {
+            "json" => {
+                serde_json::to_string_pretty(&entries).map_err(|e| CommandError::Internal {
+                    reason: e.to_string(),
+                })?
+            }
+            "csv" => {
+                let mut csv = String::from("id,timestamp,kind,inputs,result,errorCode,error\n");
+                for entry in &entries {
+                    let inputs = serde_json::to_value(&entry.operation).unwrap_or(Value::Null);
+                    let kind = inputs["kind"].as_str().unwrap_or_default().to_string();
+                    let result = entry.result.as_ref().map(Value::to_string);
+                    let code = entry
+                        .error
+                        .as_ref()
+                        .and_then(|error| error["code"].as_str())
+                        .unwrap_or_default();
+                    let fields = [
+                        entry.id.to_string(),
+                        entry.timestamp.to_string(),
+                        kind,
+                        inputs.to_string(),
+                        result.unwrap_or_default(),
+                        code.to_string(),
+                        entry.error_message().unwrap_or_default(),
+                    ];
+                    let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                    csv.push_str(&row.join(","));
+                    csv.push('\n');
+                }
+                csv
+            }
+            _ => {
+                return Err(CommandError::invalid_argument(
+                    "format",
+                    format,
+                    "json | csv",
+                ))
+            }
+        };
+
+        fs::write(path, content).map_err(|e| CommandError::io(IoOperation::Write, path, e))?;
+        Ok(entries.len())
+    }
+}
+
+// CSV 字段转义：包含逗号、引号或换行时用双引号包裹，内部引号加倍
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,192 @@
+// 国际化：菜单、托盘和命令错误的提示文字都从 locales/*.json 消息目录中读取
+// 目录在编译时嵌入程序，键为 "menu.file" 这样的点分名称，值中的 {name} 为占位符
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock, RwLock};
+
+use crate::error::{CommandError, IoOperation};
+
+// 支持的界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+    #[serde(rename = "en-US")]
+    EnUs,
+}
+
+// 消息目录缺少某个键时退回到中文目录，中文目录是完整的
+const FALLBACK: Locale = Locale::ZhCn;
+
+type Catalog = HashMap<String, String>;
+
+static CATALOGS: OnceLock<HashMap<Locale, Catalog>> = OnceLock::new();
+static CURRENT: RwLock<Option<Locale>> = RwLock::new(None);
+
+impl Locale {
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::ZhCn => "zh-CN",
+            Locale::EnUs => "en-US",
+        }
+    }
+
+    // 把 "en"、"en_GB.UTF-8"、"zh-Hans-CN" 这样的语言标签映射到支持的语言
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let language = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match language.as_str() {
+            "zh" => Some(Locale::ZhCn),
+            "en" => Some(Locale::EnUs),
+            _ => None,
+        }
+    }
+
+    // 根据操作系统的语言设置推断，无法识别时使用中文
+    pub fn detect() -> Locale {
+        sys_locale::get_locales()
+            .find_map(|tag| Locale::from_tag(&tag))
+            .unwrap_or(FALLBACK)
+    }
+
+    // 当前界面语言
+    pub fn current() -> Locale {
+        if let Some(locale) = *CURRENT.read().unwrap() {
+            return locale;
+        }
+        let locale = Locale::detect();
+        *CURRENT.write().unwrap() = Some(locale);
+        locale
+    }
+
+    pub fn set_current(locale: Locale) {
+        *CURRENT.write().unwrap() = Some(locale);
+    }
+}
+
+fn catalogs() -> &'static HashMap<Locale, Catalog> {
+    CATALOGS.get_or_init(|| {
+        let sources = [
+            (Locale::ZhCn, include_str!("../locales/zh-CN.json")),
+            (Locale::EnUs, include_str!("../locales/en-US.json")),
+        ];
+        sources
+            .into_iter()
+            .map(|(locale, source)| {
+                let catalog = serde_json::from_str(source).unwrap_or_else(|e| {
+                    eprintln!("[RUST] 消息目录 {} 解析失败: {:?}", locale.tag(), e);
+                    Catalog::new()
+                });
+                (locale, catalog)
+            })
+            .collect()
+    })
+}
+
+// 查找消息模板，当前语言的目录中没有时退回中文目录
+fn template(locale: Locale, key: &str) -> Option<&'static String> {
+    let catalogs = catalogs();
+    catalogs
+        .get(&locale)
+        .and_then(|catalog| catalog.get(key))
+        .or_else(|| catalogs.get(&FALLBACK).and_then(|catalog| catalog.get(key)))
+}
+
+// 查找可选的消息，目录中没有该键时返回 None
+pub fn lookup(locale: Locale, key: &str) -> Option<String> {
+    template(locale, key).cloned()
+}
+
+// 按指定语言翻译，并把 {name} 占位符替换为 args 中的同名参数
+// 找不到键时依次退回中文目录和键名本身，保证界面上总有文字
+pub fn translate(locale: Locale, key: &str, args: &Map<String, Value>) -> String {
+    let Some(template) = template(locale, key) else {
+        eprintln!("[RUST] 消息目录中缺少: {}", key);
+        return key.to_string();
+    };
+
+    // 逐段扫描替换，参数值中即使含有 {xxx} 也不会被再次替换
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        text.push_str(&rest[..start]);
+        match args.get(&rest[start + 1..end]) {
+            Some(Value::String(value)) => text.push_str(value),
+            Some(Value::Null) => {}
+            Some(value) => text.push_str(&value.to_string()),
+            // 没有对应参数的占位符原样保留
+            None => text.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+    text
+}
+
+// 按当前语言翻译不带参数的消息
+pub fn t(key: &str) -> String {
+    translate(Locale::current(), key, &Map::new())
+}
+
+// 按当前语言翻译带参数的消息，args 为 json!({ "name": value }) 这样的对象
+pub fn t_with(key: &str, args: Value) -> String {
+    match args {
+        Value::Object(args) => translate(Locale::current(), key, &args),
+        _ => translate(Locale::current(), key, &Map::new()),
+    }
+}
+
+// 用户选择的语言，保存在应用数据目录下；为 None 时跟随系统语言
+pub struct LocaleSettings {
+    file: PathBuf,
+    selected: Mutex<Option<Locale>>,
+}
+
+impl LocaleSettings {
+    // 加载保存的设置并立即生效；文件不存在或内容损坏时跟随系统
+    pub fn load(file: PathBuf) -> Self {
+        let selected = fs::read_to_string(&file).ok().and_then(|text| {
+            match serde_json::from_str::<Option<Locale>>(&text) {
+                Ok(locale) => locale,
+                Err(e) => {
+                    eprintln!("[RUST] 语言设置解析失败，将跟随系统语言: {:?}", e);
+                    None
+                }
+            }
+        });
+        Locale::set_current(selected.unwrap_or_else(Locale::detect));
+
+        LocaleSettings {
+            file,
+            selected: Mutex::new(selected),
+        }
+    }
+
+    pub fn selected(&self) -> Option<Locale> {
+        *self.selected.lock().unwrap()
+    }
+
+    // 切换语言并保存设置，返回实际生效的语言
+    pub fn select(&self, locale: Option<Locale>) -> Result<Locale, CommandError> {
+        let write_error = |e| CommandError::io(IoOperation::Write, &self.file, e);
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir).map_err(write_error)?;
+        }
+        let text = serde_json::to_string(&locale).unwrap_or_else(|_| "null".to_string());
+        fs::write(&self.file, text).map_err(write_error)?;
+
+        *self.selected.lock().unwrap() = locale;
+        let effective = locale.unwrap_or_else(Locale::detect);
+        Locale::set_current(effective);
+        Ok(effective)
+    }
+}
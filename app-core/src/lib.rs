@@ -0,0 +1,26 @@
+// 两个应用共用的模块：计算器、统计分析、计算历史、安全保存、命令错误和消息目录
+// 这里的代码不依赖 tauri（启用 tauri 特性时除外），可以直接用 cargo test 测试；
+// 菜单、托盘、文档编辑等只属于 lesson-07-tray-menu-app 的功能留在该应用中
+pub mod atomic_write;
+pub mod csv_import;
+pub mod decimal;
+pub mod error;
+pub mod expression;
+pub mod histogram;
+pub mod history;
+pub mod i18n;
+pub mod outliers;
+pub mod regression;
+pub mod statistics;
+#[cfg(feature = "tauri")]
+pub mod stream_sessions;
+pub mod stream_stats;
+
+// 当前 Unix 时间戳（秒），get_timestamp 命令和计算历史共用
+pub fn current_timestamp() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,331 @@
+// 离群值检测：Z 分数、修正 Z 分数（MAD）和 IQR 栅栏三种方法
+// 返回被标记的下标和数值，以及剔除离群值后重新计算的统计信息
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommandError;
+use crate::statistics::{self, NonFinitePolicy, Statistics, StatisticsOptions};
+
+// 修正 Z 分数中使 MAD 与正态分布标准差一致的常数
+const MAD_SCALE: f64 = 0.6745;
+// MAD 为 0 时改用平均绝对偏差，对应的常数
+const MEAN_AD_SCALE: f64 = 1.253314;
+
+// 检测方法及其阈值，阈值省略时使用常见的默认值
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "camelCase")]
+pub enum OutlierMethod {
+    // |x - 均值| / 标准差 > threshold（默认 3）
+    ZScore { threshold: Option<f64> },
+    // 0.6745·|x - 中位数| / MAD > threshold（默认 3.5）
+    ModifiedZScore { threshold: Option<f64> },
+    // x < Q1 - multiplier·IQR 或 x > Q3 + multiplier·IQR（默认 1.5）
+    Iqr { multiplier: Option<f64> },
+}
+
+impl Default for OutlierMethod {
+    fn default() -> Self {
+        OutlierMethod::Iqr { multiplier: None }
+    }
+}
+
+impl OutlierMethod {
+    // 阈值或倍数，省略时取默认值；必须是有限的正数，否则正常值区间没有意义
+    fn parameter(self) -> Result<f64, CommandError> {
+        let (name, value, default) = match self {
+            OutlierMethod::ZScore { threshold } => ("method.threshold", threshold, 3.0),
+            OutlierMethod::ModifiedZScore { threshold } => ("method.threshold", threshold, 3.5),
+            OutlierMethod::Iqr { multiplier } => ("method.multiplier", multiplier, 1.5),
+        };
+        let value = value.unwrap_or(default);
+        if !(value > 0.0 && value.is_finite()) {
+            return Err(CommandError::invalid_argument(
+                name,
+                value,
+                "> 0 and finite",
+            ));
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlierOptions {
+    #[serde(default)]
+    pub method: OutlierMethod,
+    // 计算统计信息时使用的选项；NaN / 无穷大无法参与检测，Propagate 策略按 Reject 处理
+    #[serde(default)]
+    pub statistics: StatisticsOptions,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Outlier {
+    // 在原始输入数组中的下标（从 0 开始）
+    pub index: usize,
+    pub value: f64,
+    // Z 分数方法为（修正）Z 分数；IQR 方法为超出栅栏的距离相对 IQR 的倍数
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlierReport {
+    pub method: OutlierMethod,
+    // 判定为正常值的区间，区间外的数据即为离群值
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub outliers: Vec<Outlier>,
+    // 全部数据的统计信息
+    pub statistics: Statistics,
+    // 剔除离群值后重新计算的统计信息；全部被剔除时为 None
+    pub cleaned_statistics: Option<Statistics>,
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    statistics::percentile_sorted(&sorted, 50.0)
+}
+
+pub fn detect(numbers: &[f64], options: &OutlierOptions) -> Result<OutlierReport, CommandError> {
+    let parameter = options.method.parameter()?;
+    let mut stats_options = options.statistics.clone();
+    if stats_options.non_finite == NonFinitePolicy::Propagate {
+        stats_options.non_finite = NonFinitePolicy::Reject;
+    }
+    let statistics = statistics::compute(numbers, &stats_options)?;
+
+    // 保留原始下标，Skip 策略下跳过的 NaN / 无穷大不参与检测
+    let indexed: Vec<(usize, f64)> = numbers
+        .iter()
+        .cloned()
+        .enumerate()
+        .filter(|(_, x)| x.is_finite())
+        .collect();
+    let values: Vec<f64> = indexed.iter().map(|&(_, x)| x).collect();
+
+    // 每种方法给出一个打分函数和正常值区间；离散程度为 0 时不标记任何离群值
+    let (lower_bound, upper_bound, score): (f64, f64, Box<dyn Fn(f64) -> f64>) =
+        match options.method {
+            OutlierMethod::ZScore { .. } => {
+                let threshold = parameter;
+                let mean = statistics.average;
+                let std_dev = statistics.population_std_dev;
+                if std_dev == 0.0 {
+                    (mean, mean, Box::new(|_| 0.0))
+                } else {
+                    (
+                        mean - threshold * std_dev,
+                        mean + threshold * std_dev,
+                        Box::new(move |x| (x - mean) / std_dev),
+                    )
+                }
+            }
+            OutlierMethod::ModifiedZScore { .. } => {
+                let threshold = parameter;
+                let center = statistics.median;
+                let deviations: Vec<f64> = values.iter().map(|x| (x - center).abs()).collect();
+                let mad = median(&deviations);
+                // MAD 为 0（超过一半的数据相同）时退回平均绝对偏差
+                let spread = if mad > 0.0 {
+                    mad / MAD_SCALE
+                } else {
+                    MEAN_AD_SCALE * deviations.iter().sum::<f64>() / deviations.len() as f64
+                };
+                if spread == 0.0 {
+                    (center, center, Box::new(|_| 0.0))
+                } else {
+                    (
+                        center - threshold * spread,
+                        center + threshold * spread,
+                        Box::new(move |x| (x - center) / spread),
+                    )
+                }
+            }
+            OutlierMethod::Iqr { .. } => {
+                let multiplier = parameter;
+                let (q1, q3, iqr) = (statistics.q1, statistics.q3, statistics.iqr);
+                let lower = q1 - multiplier * iqr;
+                let upper = q3 + multiplier * iqr;
+                let scale = if iqr > 0.0 { iqr } else { 1.0 };
+                (
+                    lower,
+                    upper,
+                    Box::new(move |x| {
+                        if x < lower {
+                            (x - lower) / scale
+                        } else if x > upper {
+                            (x - upper) / scale
+                        } else {
+                            0.0
+                        }
+                    }),
+                )
+            }
+        };
+
+    let mut outliers = Vec::new();
+    let mut inliers = Vec::new();
+    for &(index, value) in &indexed {
+        if value < lower_bound || value > upper_bound {
+            outliers.push(Outlier {
+                index,
+                value,
+                score: score(value),
+            });
+        } else {
+            inliers.push(value);
+        }
+    }
+
+    let cleaned_statistics = if outliers.is_empty() {
+        Some(statistics.clone())
+    } else {
+        statistics::compute(&inliers, &stats_options).ok()
+    };
+
+    Ok(OutlierReport {
+        method: options.method,
+        lower_bound,
+        upper_bound,
+        outliers,
+        statistics,
+        cleaned_statistics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect_with(numbers: &[f64], method: OutlierMethod) -> OutlierReport {
+        let options = OutlierOptions {
+            method,
+            ..Default::default()
+        };
+        detect(numbers, &options).unwrap()
+    }
+
+    fn indexes(report: &OutlierReport) -> Vec<usize> {
+        report.outliers.iter().map(|o| o.index).collect()
+    }
+
+    #[test]
+    fn iqr_fences_flag_values_outside() {
+        let numbers = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 100.0];
+        let report = detect_with(&numbers, OutlierMethod::default());
+        // Q1 = 3, Q3 = 7, IQR = 4
+        assert_eq!((report.lower_bound, report.upper_bound), (-3.0, 13.0));
+        assert_eq!(indexes(&report), vec![8]);
+        assert_eq!(report.outliers[0].score, (100.0 - 13.0) / 4.0);
+        assert_eq!(report.cleaned_statistics.unwrap().max, 8.0);
+    }
+
+    #[test]
+    fn z_score_uses_mean_and_standard_deviation() {
+        let mut numbers = vec![10.0; 20];
+        numbers.push(50.0);
+        let report = detect_with(
+            &numbers,
+            OutlierMethod::ZScore {
+                threshold: Some(3.0),
+            },
+        );
+        assert_eq!(indexes(&report), vec![20]);
+        assert!(report.outliers[0].score > 3.0);
+
+        let report = detect_with(&[5.0; 4], OutlierMethod::ZScore { threshold: None });
+        assert!(report.outliers.is_empty());
+    }
+
+    #[test]
+    fn modified_z_score_falls_back_when_mad_is_zero() {
+        // 超过一半的数据相同，MAD 为 0，改用平均绝对偏差
+        let numbers = [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 10.0];
+        let report = detect_with(&numbers, OutlierMethod::ModifiedZScore { threshold: None });
+        let spread = MEAN_AD_SCALE * 9.0 / 7.0;
+        assert!((report.upper_bound - (1.0 + 3.5 * spread)).abs() < 1e-12);
+        assert_eq!(indexes(&report), vec![6]);
+
+        // 所有数据都相同时不标记任何离群值
+        let report = detect_with(&[2.0; 5], OutlierMethod::ModifiedZScore { threshold: None });
+        assert!(report.outliers.is_empty());
+        assert_eq!((report.lower_bound, report.upper_bound), (2.0, 2.0));
+    }
+
+    #[test]
+    fn skipped_values_keep_original_indexes() {
+        let options = OutlierOptions {
+            method: OutlierMethod::default(),
+            statistics: StatisticsOptions {
+                percentiles: Vec::new(),
+                non_finite: NonFinitePolicy::Skip,
+            },
+        };
+        let numbers = [1.0, f64::NAN, 2.0, 3.0, 4.0, 100.0];
+        let report = detect(&numbers, &options).unwrap();
+        assert_eq!(indexes(&report), vec![5]);
+        assert_eq!(report.statistics.skipped, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_thresholds() {
+        for (method, name) in [
+            (
+                OutlierMethod::ZScore {
+                    threshold: Some(0.0),
+                },
+                "method.threshold",
+            ),
+            (
+                OutlierMethod::ZScore {
+                    threshold: Some(f64::INFINITY),
+                },
+                "method.threshold",
+            ),
+            (
+                OutlierMethod::ModifiedZScore {
+                    threshold: Some(-1.0),
+                },
+                "method.threshold",
+            ),
+            (
+                OutlierMethod::ModifiedZScore {
+                    threshold: Some(f64::NAN),
+                },
+                "method.threshold",
+            ),
+            (
+                OutlierMethod::Iqr {
+                    multiplier: Some(0.0),
+                },
+                "method.multiplier",
+            ),
+        ] {
+            let options = OutlierOptions {
+                method,
+                ..Default::default()
+            };
+            match detect(&[1.0, 2.0, 3.0], &options) {
+                Err(CommandError::InvalidArgument {
+                    name: actual,
+                    expected,
+                    ..
+                }) => {
+                    assert_eq!(actual, name);
+                    assert_eq!(expected, "> 0 and finite");
+                }
+                other => panic!("{:?} 应该被拒绝，实际为 {:?}", method, other),
+            }
+        }
+    }
+
+    #[test]
+    fn empty_input_is_an_error() {
+        assert!(matches!(
+            detect(&[], &OutlierOptions::default()),
+            Err(CommandError::EmptyInput { .. })
+        ));
+    }
+}
@@ -0,0 +1,395 @@
+// 回归分析：对成对的 x / y 数据拟合线性、多项式、指数和对数模型
+// 最小二乘问题用 Householder QR 分解求解，比直接解正规方程数值上更稳定
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommandError;
+
+// 多项式的最高次数，次数过高时范德蒙矩阵严重病态
+const MAX_DEGREE: usize = 10;
+
+// 模型类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RegressionKind {
+    // y = c0 + c1·x
+    #[default]
+    Linear,
+    // y = c0 + c1·x + c2·x² + … + cN·x^N
+    Polynomial { degree: usize },
+    // y = a·e^(b·x)，要求所有 y > 0
+    Exponential,
+    // y = a + b·ln(x)，要求所有 x > 0
+    Logarithmic,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegressionOptions {
+    #[serde(default)]
+    pub model: RegressionKind,
+    // 拟合后顺便计算这些 x 处的预测值
+    #[serde(default)]
+    pub predict: Vec<f64>,
+}
+
+// 拟合得到的模型，可以原样传回 predict_regression 命令继续做预测
+// coefficients 的含义：线性 / 多项式为 [c0, c1, …]，指数和对数为 [a, b]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FittedModel {
+    #[serde(flatten)]
+    pub kind: RegressionKind,
+    pub coefficients: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prediction {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegressionResult {
+    pub model: FittedModel,
+    // 人类可读的方程，例如 "y = 1.5 + 2·x"
+    pub equation: String,
+    // 决定系数，在原始 y 尺度上计算；所有 y 都相同时为 None
+    pub r_squared: Option<f64>,
+    // 每个数据点的拟合值和残差（y - 拟合值）
+    pub fitted: Vec<f64>,
+    pub residuals: Vec<f64>,
+    pub predictions: Vec<Prediction>,
+}
+
+impl FittedModel {
+    // 计算模型在 x 处的预测值
+    pub fn predict(&self, x: f64) -> f64 {
+        let c = &self.coefficients;
+        match self.kind {
+            RegressionKind::Linear | RegressionKind::Polynomial { .. } => {
+                // 霍纳法则求多项式的值
+                c.iter().rev().fold(0.0, |acc, &coefficient| acc * x + coefficient)
+            }
+            RegressionKind::Exponential => c[0] * (c[1] * x).exp(),
+            RegressionKind::Logarithmic => c[0] + c[1] * x.ln(),
+        }
+    }
+
+    fn equation(&self) -> String {
+        let c = &self.coefficients;
+        match self.kind {
+            RegressionKind::Linear | RegressionKind::Polynomial { .. } => {
+                let mut equation = format!("y = {}", c[0]);
+                for (power, &coefficient) in c.iter().enumerate().skip(1) {
+                    let variable = if power == 1 {
+                        "x".to_string()
+                    } else {
+                        format!("x^{}", power)
+                    };
+                    equation.push_str(&format!("{}·{}", signed(coefficient), variable));
+                }
+                equation
+            }
+            RegressionKind::Exponential => format!("y = {}·e^({}·x)", c[0], c[1]),
+            RegressionKind::Logarithmic => format!("y = {}{}·ln(x)", c[0], signed(c[1])),
+        }
+    }
+}
+
+// 校验外部传入的模型（系数个数必须与模型类型匹配），再在多个 x 处做预测
+pub fn predict(model: &FittedModel, x: &[f64]) -> Result<Vec<Prediction>, CommandError> {
+    let expected = match model.kind {
+        RegressionKind::Polynomial { degree } => degree + 1,
+        _ => 2,
+    };
+    if model.coefficients.len() != expected {
+        return Err(CommandError::invalid_argument(
+            "model.coefficients",
+            format!("{:?}", model.coefficients),
+            format!("len == {}", expected),
+        ));
+    }
+    Ok(x.iter()
+        .map(|&xi| Prediction {
+            x: xi,
+            y: model.predict(xi),
+        })
+        .collect())
+}
+
+// 把系数格式化为 " + 2" 或 " - 2"，用于拼接方程
+fn signed(value: f64) -> String {
+    if value < 0.0 {
+        format!(" - {}", -value)
+    } else {
+        format!(" + {}", value)
+    }
+}
+
+// 用 Householder QR 分解求解最小二乘问题 min ||A·β - b||
+// rows 为设计矩阵 A 的各行（m × n，要求 m ≥ n）
+fn least_squares(mut rows: Vec<Vec<f64>>, mut b: Vec<f64>) -> Result<Vec<f64>, CommandError> {
+    let m = rows.len();
+    let n = rows[0].len();
+    let scale = rows
+        .iter()
+        .flatten()
+        .fold(0.0f64, |acc, value| acc.max(value.abs()))
+        .max(1.0);
+
+    for k in 0..n {
+        let norm = (k..m).map(|i| rows[i][k] * rows[i][k]).sum::<f64>().sqrt();
+        if norm <= f64::EPSILON * scale * m as f64 {
+            return Err(CommandError::FitFailed);
+        }
+        let alpha = if rows[k][k] > 0.0 { -norm } else { norm };
+
+        // Householder 向量 v = x - alpha·e1，再单位化
+        let mut v: Vec<f64> = (k..m).map(|i| rows[i][k]).collect();
+        v[0] -= alpha;
+        let v_norm = v.iter().map(|value| value * value).sum::<f64>().sqrt();
+        for value in &mut v {
+            *value /= v_norm;
+        }
+
+        // 对 A 的剩余列和 b 施加反射 H = I - 2·v·vᵀ
+        for j in k..n {
+            let dot: f64 = rows[k..].iter().zip(&v).map(|(row, vi)| vi * row[j]).sum();
+            for (row, vi) in rows[k..].iter_mut().zip(&v) {
+                row[j] -= 2.0 * vi * dot;
+            }
+        }
+        let dot: f64 = b[k..].iter().zip(&v).map(|(bi, vi)| vi * bi).sum();
+        for (bi, vi) in b[k..].iter_mut().zip(&v) {
+            *bi -= 2.0 * vi * dot;
+        }
+    }
+
+    // 回代求解上三角方程 R·β = Qᵀ·b
+    let mut beta = vec![0.0; n];
+    for k in (0..n).rev() {
+        let sum: f64 = ((k + 1)..n).map(|j| rows[k][j] * beta[j]).sum();
+        beta[k] = (b[k] - sum) / rows[k][k];
+    }
+    Ok(beta)
+}
+
+pub fn fit(
+    x: &[f64],
+    y: &[f64],
+    options: &RegressionOptions,
+) -> Result<RegressionResult, CommandError> {
+    if x.len() != y.len() {
+        return Err(CommandError::LengthMismatch {
+            x: x.len(),
+            y: y.len(),
+        });
+    }
+    if let Some(i) = (0..x.len()).find(|&i| !x[i].is_finite() || !y[i].is_finite()) {
+        return Err(CommandError::InvalidNumber {
+            index: Some(i),
+            value: None,
+        });
+    }
+
+    let kind = options.model;
+    let parameters = match kind {
+        RegressionKind::Polynomial { degree } => {
+            if degree == 0 || degree > MAX_DEGREE {
+                return Err(CommandError::invalid_argument(
+                    "model.degree",
+                    degree,
+                    format!("1..={}", MAX_DEGREE),
+                ));
+            }
+            degree + 1
+        }
+        _ => 2,
+    };
+    if x.len() < parameters {
+        return Err(CommandError::InsufficientData {
+            required: parameters,
+            actual: x.len(),
+        });
+    }
+
+    // 把指数和对数模型转换为线性问题：ln(y) = ln(a) + b·x，y = a + b·ln(x)
+    let (design, target): (Vec<Vec<f64>>, Vec<f64>) = match kind {
+        RegressionKind::Linear | RegressionKind::Polynomial { .. } => (
+            x.iter()
+                .map(|&xi| (0..parameters).map(|p| xi.powi(p as i32)).collect())
+                .collect(),
+            y.to_vec(),
+        ),
+        RegressionKind::Exponential => {
+            if let Some(&yi) = y.iter().find(|&&yi| yi <= 0.0) {
+                return Err(CommandError::invalid_argument("y", yi, "> 0"));
+            }
+            (
+                x.iter().map(|&xi| vec![1.0, xi]).collect(),
+                y.iter().map(|yi| yi.ln()).collect(),
+            )
+        }
+        RegressionKind::Logarithmic => {
+            if let Some(&xi) = x.iter().find(|&&xi| xi <= 0.0) {
+                return Err(CommandError::invalid_argument("x", xi, "> 0"));
+            }
+            (x.iter().map(|&xi| vec![1.0, xi.ln()]).collect(), y.to_vec())
+        }
+    };
+
+    let mut coefficients = least_squares(design, target)?;
+    if kind == RegressionKind::Exponential {
+        coefficients[0] = coefficients[0].exp();
+    }
+    let model = FittedModel { kind, coefficients };
+
+    let fitted: Vec<f64> = x.iter().map(|&xi| model.predict(xi)).collect();
+    let residuals: Vec<f64> = y.iter().zip(&fitted).map(|(yi, fi)| yi - fi).collect();
+    let mean = y.iter().sum::<f64>() / y.len() as f64;
+    let ss_total: f64 = y.iter().map(|yi| (yi - mean).powi(2)).sum();
+    let ss_residual: f64 = residuals.iter().map(|r| r * r).sum();
+    let r_squared = (ss_total > 0.0).then(|| 1.0 - ss_residual / ss_total);
+
+    let predictions = options
+        .predict
+        .iter()
+        .map(|&xi| Prediction {
+            x: xi,
+            y: model.predict(xi),
+        })
+        .collect();
+
+    Ok(RegressionResult {
+        equation: model.equation(),
+        model,
+        r_squared,
+        fitted,
+        residuals,
+        predictions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fit_model(
+        x: &[f64],
+        y: &[f64],
+        model: RegressionKind,
+    ) -> Result<RegressionResult, CommandError> {
+        let options = RegressionOptions {
+            model,
+            predict: Vec::new(),
+        };
+        fit(x, y, &options)
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn fits_exact_linear_and_polynomial_data() {
+        let x = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let y: Vec<f64> = x.iter().map(|xi| 1.0 + 2.0 * xi).collect();
+        let result = fit_model(&x, &y, RegressionKind::Linear).unwrap();
+        assert_close(&result.model.coefficients, &[1.0, 2.0]);
+        assert!((result.r_squared.unwrap() - 1.0).abs() < 1e-12);
+        assert_close(&result.residuals, &[0.0; 5]);
+
+        let y: Vec<f64> = x.iter().map(|xi| 3.0 - xi + 0.5 * xi * xi).collect();
+        let result = fit_model(&x, &y, RegressionKind::Polynomial { degree: 2 }).unwrap();
+        assert_close(&result.model.coefficients, &[3.0, -1.0, 0.5]);
+    }
+
+    #[test]
+    fn fits_exponential_and_logarithmic_models() {
+        let x = [1.0, 2.0, 3.0, 4.0];
+        let y: Vec<f64> = x.iter().map(|xi: &f64| 2.0 * (0.5 * xi).exp()).collect();
+        let result = fit_model(&x, &y, RegressionKind::Exponential).unwrap();
+        assert_close(&result.model.coefficients, &[2.0, 0.5]);
+
+        let y: Vec<f64> = x.iter().map(|xi: &f64| 1.0 - 3.0 * xi.ln()).collect();
+        let result = fit_model(&x, &y, RegressionKind::Logarithmic).unwrap();
+        assert_close(&result.model.coefficients, &[1.0, -3.0]);
+    }
+
+    #[test]
+    fn formats_equations_and_predictions() {
+        let model = FittedModel {
+            kind: RegressionKind::Polynomial { degree: 2 },
+            coefficients: vec![1.0, -2.0, 0.5],
+        };
+        assert_eq!(model.equation(), "y = 1 - 2·x + 0.5·x^2");
+        let predictions = predict(&model, &[2.0]).unwrap();
+        assert_eq!(predictions[0].y, -1.0);
+    }
+
+    #[test]
+    fn singular_design_matrix_fails_to_fit() {
+        // 所有 x 都相同时，设计矩阵的列线性相关
+        let result = fit_model(&[2.0; 4], &[1.0, 2.0, 3.0, 4.0], RegressionKind::Linear);
+        assert!(matches!(result, Err(CommandError::FitFailed)));
+
+        // 只有两个不同的 x，无法确定二次项
+        let result = fit_model(
+            &[1.0, 1.0, 2.0, 2.0],
+            &[1.0, 1.5, 2.0, 2.5],
+            RegressionKind::Polynomial { degree: 2 },
+        );
+        assert!(matches!(result, Err(CommandError::FitFailed)));
+    }
+
+    #[test]
+    fn constant_y_has_no_r_squared() {
+        let result = fit_model(&[1.0, 2.0, 3.0], &[5.0; 3], RegressionKind::Linear).unwrap();
+        assert_eq!(result.r_squared, None);
+    }
+
+    #[test]
+    fn validates_input() {
+        assert!(matches!(
+            fit_model(&[1.0, 2.0], &[1.0], RegressionKind::Linear),
+            Err(CommandError::LengthMismatch { x: 2, y: 1 })
+        ));
+        assert!(matches!(
+            fit_model(&[1.0], &[1.0], RegressionKind::Linear),
+            Err(CommandError::InsufficientData {
+                required: 2,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            fit_model(
+                &[1.0, 2.0, 3.0],
+                &[1.0, f64::NAN, 3.0],
+                RegressionKind::Linear
+            ),
+            Err(CommandError::InvalidNumber { index: Some(1), .. })
+        ));
+        assert!(matches!(
+            fit_model(&[1.0, 2.0], &[1.0, 0.0], RegressionKind::Exponential),
+            Err(CommandError::InvalidArgument { name, .. }) if name == "y"
+        ));
+        assert!(matches!(
+            fit_model(&[1.0, 2.0], &[1.0, 2.0], RegressionKind::Polynomial { degree: 0 }),
+            Err(CommandError::InvalidArgument { name, .. }) if name == "model.degree"
+        ));
+
+        let model = FittedModel {
+            kind: RegressionKind::Polynomial { degree: 2 },
+            coefficients: vec![1.0, 2.0],
+        };
+        assert!(matches!(
+            predict(&model, &[1.0]),
+            Err(CommandError::InvalidArgument { expected, .. }) if expected == "len == 3"
+        ));
+    }
+}
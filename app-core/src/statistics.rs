@@ -0,0 +1,302 @@
+// 描述性统计：计数、均值、中位数、众数、方差、标准差、四分位数、偏度、峰度和任意百分位数
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommandError;
+
+// NaN / 无穷大的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NonFinitePolicy {
+    // 遇到 NaN 或无穷大直接报错（默认）
+    #[default]
+    Reject,
+    // 跳过 NaN 和无穷大，只统计有效数字
+    Skip,
+    // 原样参与计算，结果中可能出现 NaN / 无穷大（序列化为 JSON 时为 null）
+    Propagate,
+}
+
+// 统计选项，所有字段都可以省略
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsOptions {
+    // 额外需要计算的百分位数，取值范围 0 ~ 100
+    #[serde(default)]
+    pub percentiles: Vec<f64>,
+    #[serde(default)]
+    pub non_finite: NonFinitePolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Percentile {
+    pub percentile: f64,
+    pub value: f64,
+}
+
+// 统计信息结构体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statistics {
+    pub count: usize,
+    // 按 Skip 策略被跳过的 NaN / 无穷大个数
+    pub skipped: usize,
+    pub sum: f64,
+    pub average: f64,
+    pub max: f64,
+    pub min: f64,
+    pub median: f64,
+    // 出现次数最多的值（可能有多个）；所有值都只出现一次时为空
+    pub mode: Vec<f64>,
+    pub population_variance: f64,
+    pub population_std_dev: f64,
+    // 样本方差 / 标准差需要至少 2 个数
+    pub sample_variance: Option<f64>,
+    pub sample_std_dev: Option<f64>,
+    pub q1: f64,
+    pub q3: f64,
+    pub iqr: f64,
+    // 偏度与超额峰度（总体矩估计），所有值相同时没有意义，返回 None
+    pub skewness: Option<f64>,
+    pub kurtosis: Option<f64>,
+    pub percentiles: Vec<Percentile>,
+}
+
+// 按 NonFinitePolicy 过滤输入，返回可以参与统计的数字和被跳过的个数
+pub fn prepare(
+    numbers: &[f64],
+    policy: NonFinitePolicy,
+) -> Result<(Vec<f64>, usize), CommandError> {
+    match policy {
+        NonFinitePolicy::Reject => {
+            if let Some(index) = numbers.iter().position(|n| !n.is_finite()) {
+                return Err(CommandError::InvalidNumber {
+                    index: Some(index),
+                    value: Some(numbers[index].to_string()),
+                });
+            }
+            Ok((numbers.to_vec(), 0))
+        }
+        NonFinitePolicy::Skip => {
+            let values: Vec<f64> = numbers.iter().cloned().filter(|n| n.is_finite()).collect();
+            let skipped = numbers.len() - values.len();
+            Ok((values, skipped))
+        }
+        NonFinitePolicy::Propagate => Ok((numbers.to_vec(), 0)),
+    }
+}
+
+// 在已排序的数据上按线性插值计算百分位数（与 numpy 默认方法一致）
+pub fn percentile_sorted(sorted: &[f64], percentile: f64) -> f64 {
+    if sorted.len() == 1 {
+        return sorted[0];
+    }
+    let rank = percentile / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f64;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * fraction
+}
+
+fn modes(sorted: &[f64]) -> Vec<f64> {
+    let mut best_count = 0;
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < sorted.len() {
+        let mut j = i + 1;
+        while j < sorted.len() && sorted[j] == sorted[i] {
+            j += 1;
+        }
+        let run = j - i;
+        if run > best_count {
+            best_count = run;
+            result.clear();
+            result.push(sorted[i]);
+        } else if run == best_count {
+            result.push(sorted[i]);
+        }
+        i = j;
+    }
+    if best_count == 1 && sorted.len() > 1 {
+        result.clear();
+    }
+    result
+}
+
+// 计算完整的描述性统计；空输入返回错误，而不是一组全零的结果
+pub fn compute(numbers: &[f64], options: &StatisticsOptions) -> Result<Statistics, CommandError> {
+    if numbers.is_empty() {
+        return Err(CommandError::EmptyInput { skipped: 0 });
+    }
+    for &p in &options.percentiles {
+        if !(0.0..=100.0).contains(&p) {
+            return Err(CommandError::invalid_argument("percentiles", p, "0..=100"));
+        }
+    }
+
+    let (values, skipped) = prepare(numbers, options.non_finite)?;
+    if values.is_empty() {
+        return Err(CommandError::EmptyInput { skipped });
+    }
+
+    let mut sorted = values.clone();
+    sorted.sort_by(f64::total_cmp);
+
+    let count = values.len();
+    let n = count as f64;
+    let sum: f64 = values.iter().sum();
+    let average = sum / n;
+    // f64::max / min 会忽略 NaN，所以按 Propagate 策略保留了 NaN 时直接返回 NaN；
+    // 否则排序后的首尾就是最小值和最大值
+    let (min, max) = if values.iter().any(|x| x.is_nan()) {
+        (f64::NAN, f64::NAN)
+    } else {
+        (sorted[0], sorted[count - 1])
+    };
+
+    // 中心矩：m2 为总体方差，m3、m4 用于偏度和峰度
+    let (mut m2, mut m3, mut m4) = (0.0, 0.0, 0.0);
+    for &x in &values {
+        let d = x - average;
+        let d2 = d * d;
+        m2 += d2;
+        m3 += d2 * d;
+        m4 += d2 * d2;
+    }
+    let sample_variance = (count > 1).then(|| m2 / (n - 1.0));
+    m2 /= n;
+    m3 /= n;
+    m4 /= n;
+
+    let q1 = percentile_sorted(&sorted, 25.0);
+    let q3 = percentile_sorted(&sorted, 75.0);
+
+    Ok(Statistics {
+        count,
+        skipped,
+        sum,
+        average,
+        max,
+        min,
+        median: percentile_sorted(&sorted, 50.0),
+        mode: modes(&sorted),
+        population_variance: m2,
+        population_std_dev: m2.sqrt(),
+        sample_variance,
+        sample_std_dev: sample_variance.map(f64::sqrt),
+        q1,
+        q3,
+        iqr: q3 - q1,
+        skewness: (m2 > 0.0).then(|| m3 / m2.powf(1.5)),
+        kurtosis: (m2 > 0.0).then(|| m4 / (m2 * m2) - 3.0),
+        percentiles: options
+            .percentiles
+            .iter()
+            .map(|&percentile| Percentile {
+                percentile,
+                value: percentile_sorted(&sorted, percentile),
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(non_finite: NonFinitePolicy) -> StatisticsOptions {
+        StatisticsOptions {
+            percentiles: Vec::new(),
+            non_finite,
+        }
+    }
+
+    #[test]
+    fn computes_descriptive_statistics() {
+        let numbers = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let options = StatisticsOptions {
+            percentiles: vec![0.0, 90.0, 100.0],
+            non_finite: NonFinitePolicy::Reject,
+        };
+        let stats = compute(&numbers, &options).unwrap();
+        assert_eq!(stats.count, 8);
+        assert_eq!(stats.sum, 40.0);
+        assert_eq!(stats.average, 5.0);
+        assert_eq!((stats.min, stats.max), (2.0, 9.0));
+        assert_eq!(stats.median, 4.5);
+        assert_eq!(stats.mode, vec![4.0]);
+        assert_eq!(stats.population_variance, 4.0);
+        assert_eq!(stats.population_std_dev, 2.0);
+        assert!((stats.sample_variance.unwrap() - 32.0 / 7.0).abs() < 1e-12);
+        assert_eq!((stats.q1, stats.q3, stats.iqr), (4.0, 5.5, 1.5));
+        let values: Vec<f64> = stats.percentiles.iter().map(|p| p.value).collect();
+        assert_eq!(values, vec![2.0, 7.6, 9.0]);
+    }
+
+    #[test]
+    fn constant_and_single_values_have_no_shape() {
+        let stats = compute(&[3.0; 4], &StatisticsOptions::default()).unwrap();
+        assert_eq!(stats.mode, vec![3.0]);
+        assert_eq!((stats.skewness, stats.kurtosis), (None, None));
+
+        let stats = compute(&[3.0], &StatisticsOptions::default()).unwrap();
+        assert_eq!(stats.sample_variance, None);
+        assert_eq!(stats.mode, vec![3.0]);
+    }
+
+    #[test]
+    fn rejects_empty_input_and_bad_percentiles() {
+        assert!(matches!(
+            compute(&[], &StatisticsOptions::default()),
+            Err(CommandError::EmptyInput { skipped: 0 })
+        ));
+        let options = StatisticsOptions {
+            percentiles: vec![101.0],
+            non_finite: NonFinitePolicy::Reject,
+        };
+        assert!(matches!(
+            compute(&[1.0], &options),
+            Err(CommandError::InvalidArgument { name, .. }) if name == "percentiles"
+        ));
+    }
+
+    #[test]
+    fn reject_policy_reports_the_first_non_finite_index() {
+        let result = compute(
+            &[1.0, f64::INFINITY, f64::NAN],
+            &options(NonFinitePolicy::Reject),
+        );
+        assert!(matches!(
+            result,
+            Err(CommandError::InvalidNumber { index: Some(1), .. })
+        ));
+    }
+
+    #[test]
+    fn skip_policy_counts_skipped_values() {
+        let numbers = [1.0, f64::NAN, 3.0, f64::NEG_INFINITY];
+        let stats = compute(&numbers, &options(NonFinitePolicy::Skip)).unwrap();
+        assert_eq!((stats.count, stats.skipped), (2, 2));
+        assert_eq!(stats.average, 2.0);
+
+        let result = compute(&[f64::NAN, f64::INFINITY], &options(NonFinitePolicy::Skip));
+        assert!(matches!(
+            result,
+            Err(CommandError::EmptyInput { skipped: 2 })
+        ));
+    }
+
+    #[test]
+    fn propagate_policy_keeps_nan_and_infinity() {
+        let numbers = [1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY];
+        let stats = compute(&numbers, &options(NonFinitePolicy::Propagate)).unwrap();
+        assert_eq!(stats.count, 4);
+        assert!(stats.max.is_nan());
+        assert!(stats.min.is_nan());
+        assert!(stats.sum.is_nan());
+
+        let numbers = [1.0, f64::INFINITY, f64::NEG_INFINITY, 2.0];
+        let stats = compute(&numbers, &options(NonFinitePolicy::Propagate)).unwrap();
+        assert_eq!((stats.min, stats.max), (f64::NEG_INFINITY, f64::INFINITY));
+    }
+}
@@ -0,0 +1,87 @@
+// 流式统计会话：每个会话持有一个累加器，推送数据后通过 tauri 通道把最新统计发送给订阅者
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::ipc::Channel;
+
+use crate::error::{CommandError, Resource};
+use crate::stream_stats::{Accumulator, StreamOptions, StreamStatistics};
+
+struct Session {
+    accumulator: Accumulator,
+    // 订阅了该会话的前端通道，每推送一批数据就发送一次最新统计
+    subscribers: Vec<Channel<StreamStatistics>>,
+}
+
+// 所有流式统计会话
+#[derive(Default)]
+pub struct StreamSessions {
+    next_id: Mutex<u64>,
+    sessions: Mutex<HashMap<u64, Session>>,
+}
+
+impl StreamSessions {
+    pub fn open(&self, options: &StreamOptions) -> Result<u64, CommandError> {
+        let accumulator = Accumulator::new(options)?;
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        self.sessions.lock().unwrap().insert(
+            id,
+            Session {
+                accumulator,
+                subscribers: Vec::new(),
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn push(&self, id: u64, numbers: &[f64]) -> Result<StreamStatistics, CommandError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(&id).ok_or_else(|| session_not_found(id))?;
+        session.accumulator.push_batch(numbers)?;
+        let snapshot = session.accumulator.snapshot(id)?;
+
+        // 发送失败说明前端已经不再监听，直接移除该订阅
+        session
+            .subscribers
+            .retain(|channel| channel.send(snapshot.clone()).is_ok());
+        Ok(snapshot)
+    }
+
+    pub fn snapshot(&self, id: u64) -> Result<StreamStatistics, CommandError> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(&id).ok_or_else(|| session_not_found(id))?;
+        session.accumulator.snapshot(id)
+    }
+
+    pub fn subscribe(
+        &self,
+        id: u64,
+        channel: Channel<StreamStatistics>,
+    ) -> Result<(), CommandError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(&id).ok_or_else(|| session_not_found(id))?;
+        session.subscribers.push(channel);
+        Ok(())
+    }
+
+    // 关闭会话并释放内存，返回最终统计（会话中没有数据时为 None）
+    pub fn close(&self, id: u64) -> Result<Option<StreamStatistics>, CommandError> {
+        let session = self
+            .sessions
+            .lock()
+            .unwrap()
+            .remove(&id)
+            .ok_or_else(|| session_not_found(id))?;
+        Ok(session.accumulator.snapshot(id).ok())
+    }
+}
+
+fn session_not_found(id: u64) -> CommandError {
+    CommandError::NotFound {
+        resource: Resource::StatsSession,
+        id: id.to_string(),
+    }
+}
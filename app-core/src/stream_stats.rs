@@ -0,0 +1,444 @@
+// 流式（增量）统计：数据分批推送，内存占用与样本数量无关
+// 均值、方差、偏度、峰度使用 Welford / Pébay 在线算法，分位数使用 P² 算法估计
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommandError;
+use crate::statistics::{self, NonFinitePolicy, Percentile};
+
+// 打开会话时的选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamOptions {
+    // 除四分位数和中位数外，额外需要估计的百分位数（0 ~ 100），打开会话后不能再修改
+    #[serde(default)]
+    pub percentiles: Vec<f64>,
+    #[serde(default)]
+    pub non_finite: NonFinitePolicy,
+}
+
+// 会话当前的统计结果；分位数为 P² 算法的估计值，样本数不足 5 个时为精确值
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamStatistics {
+    pub session_id: u64,
+    pub count: u64,
+    pub skipped: u64,
+    pub sum: f64,
+    pub average: f64,
+    pub max: f64,
+    pub min: f64,
+    pub median: f64,
+    pub population_variance: f64,
+    pub population_std_dev: f64,
+    pub sample_variance: Option<f64>,
+    pub sample_std_dev: Option<f64>,
+    pub q1: f64,
+    pub q3: f64,
+    pub iqr: f64,
+    pub skewness: Option<f64>,
+    pub kurtosis: Option<f64>,
+    pub percentiles: Vec<Percentile>,
+}
+
+// P² 分位数估计器：只保存 5 个标记点，不保存原始数据
+#[derive(Clone)]
+struct P2Quantile {
+    p: f64,
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+    // 前 5 个样本先缓存起来，凑满后再初始化标记点
+    initial: Vec<f64>,
+}
+
+impl P2Quantile {
+    fn new(percentile: f64) -> Self {
+        let p = percentile / 100.0;
+        P2Quantile {
+            p,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+            initial: Vec::with_capacity(5),
+        }
+    }
+
+    // x 必须是有限值；标记点因溢出变为 NaN 时返回错误，不会 panic
+    fn push(&mut self, x: f64) -> Result<(), CommandError> {
+        if self.initial.len() < 5 {
+            self.initial.push(x);
+            if self.initial.len() == 5 {
+                self.initial.sort_by(f64::total_cmp);
+                self.heights.copy_from_slice(&self.initial);
+            }
+            return Ok(());
+        }
+
+        // 找到 x 所在的区间，并更新两端的极值
+        let k = if x < self.heights[0] {
+            self.heights[0] = x;
+            0
+        } else if x >= self.heights[4] {
+            self.heights[4] = x;
+            3
+        } else {
+            (1..5)
+                .find(|&i| x < self.heights[i])
+                .ok_or_else(|| CommandError::Internal {
+                    reason: format!("P² 标记点无效: {:?}", self.heights),
+                })?
+                - 1
+        };
+
+        for i in (k + 1)..5 {
+            self.positions[i] += 1.0;
+        }
+        for i in 0..5 {
+            self.desired[i] += self.increments[i];
+        }
+
+        // 调整中间的 3 个标记点
+        for i in 1..4 {
+            let d = self.desired[i] - self.positions[i];
+            if (d >= 1.0 && self.positions[i + 1] - self.positions[i] > 1.0)
+                || (d <= -1.0 && self.positions[i - 1] - self.positions[i] < -1.0)
+            {
+                let d = d.signum();
+                let parabolic = self.parabolic(i, d);
+                self.heights[i] =
+                    if self.heights[i - 1] < parabolic && parabolic < self.heights[i + 1] {
+                        parabolic
+                    } else {
+                        self.linear(i, d)
+                    };
+                self.positions[i] += d;
+            }
+        }
+        Ok(())
+    }
+
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+        self.heights[i]
+            + d * (self.heights[j] - self.heights[i]) / (self.positions[j] - self.positions[i])
+    }
+
+    // 还没有任何有限值时为 NaN（序列化为 null）
+    fn estimate(&self) -> f64 {
+        if self.initial.is_empty() {
+            f64::NAN
+        } else if self.initial.len() < 5 {
+            let mut sorted = self.initial.clone();
+            sorted.sort_by(f64::total_cmp);
+            statistics::percentile_sorted(&sorted, self.p * 100.0)
+        } else {
+            self.heights[2]
+        }
+    }
+}
+
+// 在线累加器
+#[derive(Clone)]
+pub struct Accumulator {
+    count: u64,
+    skipped: u64,
+    // Neumaier 补偿求和，减少大量数据累加时的舍入误差
+    sum: f64,
+    compensation: f64,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+    min: f64,
+    max: f64,
+    non_finite: NonFinitePolicy,
+    quartiles: [P2Quantile; 3],
+    percentiles: Vec<(f64, P2Quantile)>,
+}
+
+impl Accumulator {
+    pub fn new(options: &StreamOptions) -> Result<Self, CommandError> {
+        for &p in &options.percentiles {
+            if !(0.0..=100.0).contains(&p) {
+                return Err(CommandError::invalid_argument("percentiles", p, "0..=100"));
+            }
+        }
+        Ok(Accumulator {
+            count: 0,
+            skipped: 0,
+            sum: 0.0,
+            compensation: 0.0,
+            mean: 0.0,
+            m2: 0.0,
+            m3: 0.0,
+            m4: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            non_finite: options.non_finite,
+            quartiles: [
+                P2Quantile::new(25.0),
+                P2Quantile::new(50.0),
+                P2Quantile::new(75.0),
+            ],
+            percentiles: options
+                .percentiles
+                .iter()
+                .map(|&p| (p, P2Quantile::new(p)))
+                .collect(),
+        })
+    }
+
+    // 推送一批数据，整批成功才生效：先在副本上累加，任何一步出错时原状态都不变
+    // 没有可用数据的批次（空批次，或全部按 Skip 策略跳过）返回 EmptyInput
+    pub fn push_batch(&mut self, numbers: &[f64]) -> Result<(), CommandError> {
+        let (values, skipped) = statistics::prepare(numbers, self.non_finite)?;
+        if values.is_empty() {
+            return Err(CommandError::EmptyInput { skipped });
+        }
+        let mut next = self.clone();
+        next.skipped += skipped as u64;
+        for x in values {
+            next.push(x)?;
+        }
+        *self = next;
+        Ok(())
+    }
+
+    fn push(&mut self, x: f64) -> Result<(), CommandError> {
+        let t = self.sum + x;
+        if self.sum.abs() >= x.abs() {
+            self.compensation += (self.sum - t) + x;
+        } else {
+            self.compensation += (x - t) + self.sum;
+        }
+        self.sum = t;
+
+        // Pébay 的在线高阶矩更新公式
+        let n1 = self.count as f64;
+        self.count += 1;
+        let n = self.count as f64;
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term1 = delta * delta_n * n1;
+        self.mean += delta_n;
+        self.m4 += term1 * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * self.m2
+            - 4.0 * delta_n * self.m3;
+        self.m3 += term1 * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term1;
+
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+
+        // P² 算法无法处理 NaN / 无穷大，Propagate 策略下分位数只统计有限值（与直方图、异常值检测一致）
+        if !x.is_finite() {
+            return Ok(());
+        }
+        for quantile in &mut self.quartiles {
+            quantile.push(x)?;
+        }
+        for (_, quantile) in &mut self.percentiles {
+            quantile.push(x)?;
+        }
+        Ok(())
+    }
+
+    pub fn snapshot(&self, session_id: u64) -> Result<StreamStatistics, CommandError> {
+        if self.count == 0 {
+            return Err(CommandError::EmptyInput {
+                skipped: self.skipped as usize,
+            });
+        }
+
+        let n = self.count as f64;
+        let population_variance = self.m2 / n;
+        let sample_variance = (self.count > 1).then(|| self.m2 / (n - 1.0));
+        let has_spread = self.m2 > 0.0;
+        let q1 = self.quartiles[0].estimate();
+        let q3 = self.quartiles[2].estimate();
+
+        // 0 和 100 百分位直接使用精确的最小值 / 最大值
+        let percentiles = self
+            .percentiles
+            .iter()
+            .map(|(percentile, quantile)| Percentile {
+                percentile: *percentile,
+                value: match *percentile {
+                    p if p <= 0.0 => self.min,
+                    p if p >= 100.0 => self.max,
+                    _ => quantile.estimate(),
+                },
+            })
+            .collect();
+
+        Ok(StreamStatistics {
+            session_id,
+            count: self.count,
+            skipped: self.skipped,
+            sum: self.sum + self.compensation,
+            average: self.mean,
+            max: self.max,
+            min: self.min,
+            median: self.quartiles[1].estimate(),
+            population_variance,
+            population_std_dev: population_variance.sqrt(),
+            sample_variance,
+            sample_std_dev: sample_variance.map(f64::sqrt),
+            q1,
+            q3,
+            iqr: q3 - q1,
+            skewness: has_spread.then(|| n.sqrt() * self.m3 / self.m2.powf(1.5)),
+            kurtosis: has_spread.then(|| n * self.m4 / (self.m2 * self.m2) - 3.0),
+            percentiles,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statistics::StatisticsOptions;
+
+    fn accumulator(non_finite: NonFinitePolicy, percentiles: Vec<f64>) -> Accumulator {
+        Accumulator::new(&StreamOptions {
+            percentiles,
+            non_finite,
+        })
+        .unwrap()
+    }
+
+    // 确定性的伪随机序列（线性同余），在 [0, 1000) 内分布
+    fn samples(count: usize) -> Vec<f64> {
+        let mut state: u64 = 42;
+        (0..count)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 * 1000.0
+            })
+            .collect()
+    }
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() <= tolerance * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn empty_session_has_no_statistics() {
+        let acc = accumulator(NonFinitePolicy::Reject, Vec::new());
+        assert!(matches!(
+            acc.snapshot(1),
+            Err(CommandError::EmptyInput { skipped: 0 })
+        ));
+    }
+
+    #[test]
+    fn moments_match_batch_statistics() {
+        let data = samples(5000);
+        let mut acc = accumulator(NonFinitePolicy::Reject, Vec::new());
+        for chunk in data.chunks(333) {
+            acc.push_batch(chunk).unwrap();
+        }
+        let stream = acc.snapshot(1).unwrap();
+        let exact = statistics::compute(&data, &StatisticsOptions::default()).unwrap();
+        assert_eq!(stream.count, 5000);
+        assert_eq!((stream.min, stream.max), (exact.min, exact.max));
+        assert!(close(stream.sum, exact.sum, 1e-12));
+        assert!(close(stream.average, exact.average, 1e-12));
+        assert!(close(
+            stream.population_variance,
+            exact.population_variance,
+            1e-9
+        ));
+        assert!(close(
+            stream.skewness.unwrap(),
+            exact.skewness.unwrap(),
+            1e-6
+        ));
+        assert!(close(
+            stream.kurtosis.unwrap(),
+            exact.kurtosis.unwrap(),
+            1e-6
+        ));
+    }
+
+    #[test]
+    fn p2_estimates_track_exact_quantiles() {
+        let data = samples(20_000);
+        let mut acc = accumulator(NonFinitePolicy::Reject, vec![0.0, 10.0, 90.0, 100.0]);
+        acc.push_batch(&data).unwrap();
+        let stream = acc.snapshot(1).unwrap();
+
+        let mut sorted = data.clone();
+        sorted.sort_by(f64::total_cmp);
+        let exact = |p| statistics::percentile_sorted(&sorted, p);
+        // 数据范围为 1000，P² 的估计误差应在 1% 以内
+        assert!((stream.median - exact(50.0)).abs() < 10.0);
+        assert!((stream.q1 - exact(25.0)).abs() < 10.0);
+        assert!((stream.q3 - exact(75.0)).abs() < 10.0);
+        let values: Vec<f64> = stream.percentiles.iter().map(|p| p.value).collect();
+        assert_eq!(values[0], sorted[0]);
+        assert!((values[1] - exact(10.0)).abs() < 10.0);
+        assert!((values[2] - exact(90.0)).abs() < 10.0);
+        assert_eq!(values[3], sorted[sorted.len() - 1]);
+    }
+
+    #[test]
+    fn small_sessions_use_exact_quantiles() {
+        let mut acc = accumulator(NonFinitePolicy::Reject, Vec::new());
+        acc.push_batch(&[4.0, 1.0, 3.0]).unwrap();
+        let stream = acc.snapshot(1).unwrap();
+        assert_eq!((stream.q1, stream.median, stream.q3), (2.0, 3.0, 3.5));
+    }
+
+    #[test]
+    fn rejected_batch_leaves_the_session_unchanged() {
+        let mut acc = accumulator(NonFinitePolicy::Reject, Vec::new());
+        acc.push_batch(&[1.0, 2.0]).unwrap();
+        let result = acc.push_batch(&[3.0, f64::NAN, 4.0]);
+        assert!(matches!(
+            result,
+            Err(CommandError::InvalidNumber { index: Some(1), .. })
+        ));
+        let stream = acc.snapshot(1).unwrap();
+        assert_eq!((stream.count, stream.sum), (2, 3.0));
+    }
+
+    #[test]
+    fn skipped_only_batch_is_an_error_without_side_effects() {
+        let mut acc = accumulator(NonFinitePolicy::Skip, Vec::new());
+        let result = acc.push_batch(&[f64::NAN, f64::INFINITY]);
+        assert!(matches!(
+            result,
+            Err(CommandError::EmptyInput { skipped: 2 })
+        ));
+        assert!(matches!(
+            acc.snapshot(1),
+            Err(CommandError::EmptyInput { skipped: 0 })
+        ));
+
+        acc.push_batch(&[1.0, f64::NAN, 3.0]).unwrap();
+        let stream = acc.snapshot(1).unwrap();
+        assert_eq!((stream.count, stream.skipped, stream.average), (2, 1, 2.0));
+    }
+
+    #[test]
+    fn propagate_keeps_non_finite_moments_but_finite_quantiles() {
+        let mut acc = accumulator(NonFinitePolicy::Propagate, Vec::new());
+        acc.push_batch(&[1.0, 2.0, f64::INFINITY, 3.0]).unwrap();
+        let stream = acc.snapshot(1).unwrap();
+        assert_eq!(stream.count, 4);
+        assert_eq!(stream.max, f64::INFINITY);
+        assert_eq!(stream.median, 2.0);
+    }
+}
@@ -0,0 +1,243 @@
+// 命令面板：把应用菜单、托盘菜单和 menu.json 中 commands 一节的命令统一为“动作”列表，
+// 每个动作有 ID、文字、说明和快捷键；search 按模糊匹配的得分排序，
+// 执行动作与点击菜单项走同一张分发表（见 lib.rs 中的 dispatch_action）
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::i18n::t;
+use crate::keymap::Keymap;
+use crate::menu::{self, MenuNode, MenuRegistry, NodeKind};
+
+// 动作的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionKind {
+    Menu,
+    Tray,
+    Command,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionInfo {
+    pub id: String,
+    pub label: String,
+    // 所在的菜单，例如 "视图 › 语言"；托盘菜单项和命令分别为 "托盘" 和 "命令"
+    pub category: String,
+    pub description: Option<String>,
+    pub shortcut: Option<String>,
+    pub kind: ActionKind,
+    // 被禁用的菜单项仍会列出，但不能执行
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionMatch {
+    #[serde(flatten)]
+    pub action: ActionInfo,
+    pub score: i64,
+    // label 中与查询匹配的字符位置（按字符计，从 0 开始），用于高亮显示
+    pub positions: Vec<usize>,
+}
+
+// 收集动作时的上下文
+struct Collector<'a, R: Runtime> {
+    app: &'a AppHandle<R>,
+    registry: &'a MenuRegistry,
+    // 快捷键映射中当前生效的快捷键
+    accelerators: HashMap<String, Option<String>>,
+    actions: Vec<ActionInfo>,
+}
+
+impl<R: Runtime> Collector<'_, R> {
+    fn visit(&mut self, nodes: &[MenuNode], category: &str, kind: ActionKind) {
+        for node in nodes {
+            match node.kind {
+                NodeKind::Separator => {}
+                NodeKind::Submenu => {
+                    let category = if category.is_empty() {
+                        node.text()
+                    } else {
+                        format!("{} › {}", category, node.text())
+                    };
+                    self.visit(&node.items, &category, kind);
+                }
+                NodeKind::RecentFiles => {
+                    for (id, path) in self.registry.recent_files() {
+                        let label = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| path.display().to_string());
+                        self.actions.push(ActionInfo {
+                            id,
+                            label,
+                            category: category.to_string(),
+                            description: Some(path.display().to_string()),
+                            shortcut: None,
+                            kind,
+                            enabled: true,
+                        });
+                    }
+                }
+                NodeKind::Item => {
+                    let Some(id) = node.id.clone() else {
+                        continue;
+                    };
+                    let state = menu::item_state(self.app, &id).ok();
+                    let shortcut = match self.accelerators.get(&id) {
+                        Some(accelerator) => accelerator.clone(),
+                        None => node.accelerator.clone(),
+                    };
+                    self.actions.push(ActionInfo {
+                        label: state
+                            .as_ref()
+                            .and_then(|state| state.text.clone())
+                            .unwrap_or_else(|| node.text()),
+                        category: category.to_string(),
+                        description: node.description.as_deref().map(t),
+                        shortcut,
+                        kind,
+                        enabled: state.and_then(|state| state.enabled).unwrap_or(true),
+                        id,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// 当前所有可以执行的动作，按菜单中的顺序排列
+pub fn list<R: Runtime>(app: &AppHandle<R>) -> Vec<ActionInfo> {
+    let registry = app.state::<MenuRegistry>();
+    let mut collector = Collector {
+        app,
+        registry: &registry,
+        accelerators: app.state::<Keymap>().accelerators(),
+        actions: Vec::new(),
+    };
+    let config = registry.config();
+    collector.visit(&config.menu, "", ActionKind::Menu);
+    collector.visit(&config.tray, &t("action.category.tray"), ActionKind::Tray);
+    collector.visit(
+        &config.commands,
+        &t("action.category.command"),
+        ActionKind::Command,
+    );
+    collector.actions
+}
+
+// 按查询对动作做模糊匹配并排序，得分相同时保持菜单中的顺序
+// 文字的匹配权重最高，其次是 "分类 文字" 和 ID，最后是说明；查询为空时返回全部动作
+pub fn search(actions: Vec<ActionInfo>, query: &str, limit: Option<usize>) -> Vec<ActionMatch> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    let mut matches: Vec<ActionMatch> = actions
+        .into_iter()
+        .filter_map(|action| {
+            if query.is_empty() {
+                return Some(ActionMatch {
+                    action,
+                    score: 0,
+                    positions: Vec::new(),
+                });
+            }
+            let label =
+                fuzzy_match(&query, &action.label).map(|(score, positions)| (score * 3, positions));
+            let others = [
+                Some(format!("{} {}", action.category, action.label)),
+                Some(action.id.clone()),
+                action.description.clone(),
+            ];
+            let other = others
+                .iter()
+                .zip([2, 2, 1])
+                .filter_map(|(text, weight)| {
+                    let (score, _) = fuzzy_match(&query, text.as_deref()?)?;
+                    Some(score * weight)
+                })
+                .max();
+
+            let (score, positions) = match (label, other) {
+                (Some((score, positions)), Some(other)) if score >= other => (score, positions),
+                (_, Some(other)) => (other, Vec::new()),
+                (Some((score, positions)), None) => (score, positions),
+                (None, None) => return None,
+            };
+            Some(ActionMatch {
+                action,
+                score,
+                positions,
+            })
+        })
+        .collect();
+
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    if let Some(limit) = limit {
+        matches.truncate(limit);
+    }
+    matches
+}
+
+// 模糊匹配：query 中的字符按顺序出现在 text 中即算匹配（不区分大小写）
+// 从 query 首字符的每个出现位置开始贪心匹配，取得分最高的一次；返回得分和匹配到的字符位置
+fn fuzzy_match(query: &[char], text: &str) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let first = *query.first()?;
+
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for start in (0..lower.len()).filter(|&i| lower[i] == first) {
+        let mut positions = vec![start];
+        for &c in &query[1..] {
+            let from = positions[positions.len() - 1] + 1;
+            match (from..lower.len()).find(|&i| lower[i] == c) {
+                Some(i) => positions.push(i),
+                None => break,
+            }
+        }
+        if positions.len() < query.len() {
+            // 从更靠后的位置开始也不可能匹配完整
+            break;
+        }
+        let score = score_positions(&chars, &positions);
+        if best.as_ref().is_none_or(|(best, _)| score > *best) {
+            best = Some((score, positions));
+        }
+    }
+    best
+}
+
+// 每个匹配的字符得分，连续匹配和单词开头加分，跳过的字符扣分，前缀和完全匹配额外加分
+fn score_positions(chars: &[char], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    for (k, &i) in positions.iter().enumerate() {
+        score += 10;
+        let word_start = i == 0 || {
+            let prev = chars[i - 1];
+            !prev.is_alphanumeric() || (prev.is_lowercase() && chars[i].is_uppercase())
+        };
+        if word_start {
+            score += 8;
+        }
+        if k > 0 {
+            let gap = i - positions[k - 1] - 1;
+            score += if gap == 0 { 12 } else { -(gap.min(10) as i64) };
+        }
+    }
+    if positions[0] == 0 {
+        score += 15;
+    }
+    if positions.len() == chars.len() {
+        score += 30;
+    }
+    score
+}
@@ -0,0 +1,245 @@
+// 自动保存：定时（以及窗口关闭前）把有未保存修改的文档写入应用数据目录下的 recovery 目录，
+// 每个文档一个快照文件，文件名以本次运行的会话标识开头；文档保存或关闭后删除对应的快照
+// 程序崩溃或被强制结束时快照会留下来，下次启动时列出这些来自以前会话的快照供用户恢复
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::atomic_write;
+use crate::document::{DocumentState, Documents, LineEnding};
+use crate::error::{CommandError, IoOperation, Resource};
+
+// 预览显示的最多字符数
+const PREVIEW_CHARS: usize = 80;
+
+// 快照文件的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    // 文档对应的文件，新建的文档为 None
+    pub path: Option<PathBuf>,
+    // 保存时使用的编码和 BOM，旧版本写入的快照中没有编码
+    #[serde(default)]
+    pub encoding: Option<String>,
+    #[serde(default)]
+    pub bom: bool,
+    #[serde(default)]
+    pub line_ending: LineEnding,
+    pub text: String,
+    // 写入快照的时间（Unix 时间戳，秒）
+    pub timestamp: u64,
+}
+
+// 可以恢复的快照，id 为快照文件名（不含扩展名）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryInfo {
+    pub id: String,
+    pub path: Option<PathBuf>,
+    pub timestamp: u64,
+    // 文本的字符数
+    pub length: usize,
+    // 第一行非空文本的开头部分
+    pub preview: String,
+}
+
+impl RecoveryInfo {
+    fn new(id: String, snapshot: &Snapshot) -> Self {
+        let line = snapshot
+            .text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default();
+        let mut preview: String = line.chars().take(PREVIEW_CHARS).collect();
+        if line.chars().count() > PREVIEW_CHARS {
+            preview.push('…');
+        }
+        RecoveryInfo {
+            id,
+            path: snapshot.path.clone(),
+            timestamp: snapshot.timestamp,
+            length: snapshot.text.chars().count(),
+            preview,
+        }
+    }
+}
+
+pub struct Autosave {
+    dir: PathBuf,
+    // 本次运行的会话标识（启动时间和进程号），用来区分本次写入的快照和以前留下的快照
+    session: String,
+    // 本次会话已经写入快照的文档 -> 快照对应的文档版本，版本没变时不重复写入
+    written: Mutex<HashMap<u64, u64>>,
+}
+
+// 快照 ID 只由字母、数字和 '-' 组成，防止拼出恢复目录以外的路径
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn snapshot_not_found(id: &str) -> CommandError {
+    CommandError::NotFound {
+        resource: Resource::RecoverySnapshot,
+        id: id.to_string(),
+    }
+}
+
+impl Autosave {
+    pub fn new(dir: PathBuf) -> Self {
+        Autosave {
+            dir,
+            session: format!("{}-{}", crate::current_timestamp(), std::process::id()),
+            written: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn file(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    // 本次会话中文档 document 的快照 ID
+    fn session_id(&self, document: u64) -> String {
+        format!("{}-{}", self.session, document)
+    }
+
+    fn is_leftover(&self, id: &str) -> bool {
+        valid_id(id) && !id.starts_with(&format!("{}-", self.session))
+    }
+
+    // 为有未保存修改、并且自上次快照以来改动过的文档写入快照；
+    // 已经保存、撤销回保存点或已经关闭的文档删除快照。写入失败只打印日志，下一次再试
+    pub fn snapshot(&self, documents: &Documents) {
+        let mut written = self.written.lock().unwrap();
+        let open = documents.list();
+
+        written.retain(|id, _| {
+            let dirty = open.iter().any(|info| info.id == *id && info.dirty);
+            if !dirty {
+                self.remove_file(&self.session_id(*id));
+            }
+            dirty
+        });
+
+        for info in open.iter().filter(|info| info.dirty) {
+            if written.get(&info.id) == Some(&info.version) {
+                continue;
+            }
+            let Ok(state) = documents.state(info.id) else {
+                continue;
+            };
+            match self.write(&state) {
+                Ok(()) => {
+                    written.insert(info.id, state.info.version);
+                }
+                Err(e) => eprintln!("[RUST] 写入文档 {} 的恢复快照失败: {}", info.id, e),
+            }
+        }
+    }
+
+    fn write(&self, state: &DocumentState) -> Result<(), CommandError> {
+        let file = self.file(&self.session_id(state.info.id));
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| CommandError::io(IoOperation::Write, &file, e))?;
+        let snapshot = Snapshot {
+            path: state.info.path.clone(),
+            encoding: Some(state.info.encoding.clone()),
+            bom: state.info.bom,
+            line_ending: state.info.line_ending,
+            text: state.text.clone(),
+            timestamp: crate::current_timestamp(),
+        };
+        let text = serde_json::to_string(&snapshot).map_err(|e| CommandError::Internal {
+            reason: e.to_string(),
+        })?;
+        atomic_write::write_file(&file, text.as_bytes(), 0)
+    }
+
+    fn remove_file(&self, id: &str) {
+        if let Err(e) = fs::remove_file(self.file(id)) {
+            if e.kind() != ErrorKind::NotFound {
+                eprintln!("[RUST] 删除恢复快照 {} 失败: {:?}", id, e);
+            }
+        }
+    }
+
+    // 文档保存或关闭后删除本次会话中它的快照
+    pub fn discard(&self, document: u64) {
+        self.written.lock().unwrap().remove(&document);
+        self.remove_file(&self.session_id(document));
+    }
+
+    // 恢复目录中以前的会话留下的快照 ID
+    fn leftovers(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    return None;
+                }
+                let id = path.file_stem()?.to_str()?.to_string();
+                self.is_leftover(&id).then_some(id)
+            })
+            .collect()
+    }
+
+    // 以前的会话留下的快照，最新的在前；无法解析的快照文件只打印日志并跳过
+    pub fn list(&self) -> Vec<RecoveryInfo> {
+        let mut snapshots: Vec<RecoveryInfo> = self
+            .leftovers()
+            .into_iter()
+            .filter_map(|id| match self.read(&id) {
+                Ok(snapshot) => Some(RecoveryInfo::new(id, &snapshot)),
+                Err(e) => {
+                    eprintln!("[RUST] 跳过无法读取的恢复快照 {}: {}", id, e);
+                    None
+                }
+            })
+            .collect();
+        snapshots.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(a.id.cmp(&b.id)));
+        snapshots
+    }
+
+    // 读取以前的会话留下的快照
+    pub fn read(&self, id: &str) -> Result<Snapshot, CommandError> {
+        if !self.is_leftover(id) {
+            return Err(snapshot_not_found(id));
+        }
+        let file = self.file(id);
+        let text = fs::read_to_string(&file).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => snapshot_not_found(id),
+            _ => CommandError::io(IoOperation::Read, &file, e),
+        })?;
+        serde_json::from_str(&text).map_err(|e| CommandError::ParseFailed {
+            format: "recovery snapshot".to_string(),
+            reason: e.to_string(),
+        })
+    }
+
+    // 删除以前的会话留下的一个快照（恢复之后，或用户选择丢弃）
+    pub fn remove(&self, id: &str) -> Result<(), CommandError> {
+        if !self.is_leftover(id) {
+            return Err(snapshot_not_found(id));
+        }
+        let file = self.file(id);
+        fs::remove_file(&file).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => snapshot_not_found(id),
+            _ => CommandError::io(IoOperation::Write, &file, e),
+        })
+    }
+
+    // 删除以前的会话留下的所有快照，返回删除的个数
+    pub fn clear(&self) -> usize {
+        self.leftovers()
+            .iter()
+            .filter(|id| self.remove(id).is_ok())
+            .count()
+    }
+}
@@ -0,0 +1,626 @@
+// 剪贴板：读写文本、HTML 和图片，并记录最近的剪贴板历史，可以搜索后重新粘贴
+// 系统剪贴板通过 arboard 访问；没有图形环境（例如无显示器的 Linux）时退回到进程内的剪贴板，
+// 应用内的复制粘贴仍然可用。历史只保存在内存中，不写入磁盘（剪贴板中可能有密码等敏感内容）
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::error::{CommandError, Resource};
+
+// 默认保留的历史条数，以及允许设置的最大条数
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+// 历史列表中预览文字的最大字符数
+const PREVIEW_CHARS: usize = 80;
+
+// 剪贴板内容的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClipboardKind {
+    Text,
+    Html,
+    Image,
+}
+
+// 剪贴板内容；图片为逐行排列的 RGBA 像素，每个像素 4 个字节
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ClipboardContent {
+    Text {
+        text: String,
+    },
+    // text 为不支持 HTML 的应用粘贴时使用的纯文本
+    Html {
+        html: String,
+        #[serde(default)]
+        text: Option<String>,
+    },
+    Image {
+        width: usize,
+        height: usize,
+        rgba: Vec<u8>,
+    },
+}
+
+impl ClipboardContent {
+    pub fn kind(&self) -> ClipboardKind {
+        match self {
+            ClipboardContent::Text { .. } => ClipboardKind::Text,
+            ClipboardContent::Html { .. } => ClipboardKind::Html,
+            ClipboardContent::Image { .. } => ClipboardKind::Image,
+        }
+    }
+
+    // 粘贴到文本框中的文字：HTML 没有纯文本时去掉标签，图片没有文字
+    pub fn plain_text(&self) -> Option<String> {
+        match self {
+            ClipboardContent::Text { text } => Some(text.clone()),
+            ClipboardContent::Html { html, text } => {
+                Some(text.clone().unwrap_or_else(|| strip_tags(html)))
+            }
+            ClipboardContent::Image { .. } => None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            ClipboardContent::Text { text } => text.is_empty(),
+            ClipboardContent::Html { html, .. } => html.is_empty(),
+            ClipboardContent::Image { width, height, .. } => *width == 0 || *height == 0,
+        }
+    }
+
+    fn check(&self) -> Result<(), CommandError> {
+        if let ClipboardContent::Image {
+            width,
+            height,
+            rgba,
+        } = self
+        {
+            // 尺寸过大时乘积会溢出，这样的图片不可能放进内存
+            let expected = width
+                .checked_mul(*height)
+                .and_then(|pixels| pixels.checked_mul(4))
+                .ok_or_else(|| {
+                    CommandError::invalid_argument(
+                        "height",
+                        height,
+                        format!("width * height * 4 <= {}", usize::MAX),
+                    )
+                })?;
+            if rgba.len() != expected {
+                return Err(CommandError::invalid_argument(
+                    "rgba",
+                    format!("{} bytes", rgba.len()),
+                    format!("{} bytes ({}x{} RGBA)", expected, width, height),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // 历史列表中显示的预览
+    fn preview(&self) -> String {
+        match self {
+            ClipboardContent::Image { width, height, .. } => format!("{}x{}", width, height),
+            _ => {
+                let text = self.plain_text().unwrap_or_default();
+                let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if line.chars().count() > PREVIEW_CHARS {
+                    let mut preview: String = line.chars().take(PREVIEW_CHARS).collect();
+                    preview.push('…');
+                    preview
+                } else {
+                    line
+                }
+            }
+        }
+    }
+}
+
+// 去掉 HTML 标签，只保留文字（不处理实体和脚本，够历史预览和纯文本粘贴使用）
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+// 剪贴板的实际读写，失败时返回错误说明；剪贴板中没有该类型的内容时读取结果为 None
+pub trait ClipboardBackend: Send {
+    fn read(&mut self, kind: ClipboardKind) -> Result<Option<ClipboardContent>, String>;
+    fn write(&mut self, content: &ClipboardContent) -> Result<(), String>;
+}
+
+// 系统剪贴板；Linux 上剪贴板内容由持有 arboard::Clipboard 的进程提供，所以一直保留这个实例
+pub struct SystemClipboard {
+    clipboard: arboard::Clipboard,
+}
+
+impl SystemClipboard {
+    pub fn new() -> Result<Self, String> {
+        let clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+        Ok(SystemClipboard { clipboard })
+    }
+}
+
+impl ClipboardBackend for SystemClipboard {
+    fn read(&mut self, kind: ClipboardKind) -> Result<Option<ClipboardContent>, String> {
+        let content = match kind {
+            ClipboardKind::Text => self
+                .clipboard
+                .get_text()
+                .map(|text| ClipboardContent::Text { text }),
+            ClipboardKind::Html => self.clipboard.get().html().map(|html| {
+                let text = self.clipboard.get_text().ok();
+                ClipboardContent::Html { html, text }
+            }),
+            ClipboardKind::Image => {
+                self.clipboard
+                    .get_image()
+                    .map(|image| ClipboardContent::Image {
+                        width: image.width,
+                        height: image.height,
+                        rgba: image.bytes.into_owned(),
+                    })
+            }
+        };
+        match content {
+            Ok(content) => Ok(Some(content)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn write(&mut self, content: &ClipboardContent) -> Result<(), String> {
+        let result = match content {
+            ClipboardContent::Text { text } => self.clipboard.set_text(text.as_str()),
+            ClipboardContent::Html { html, text } => {
+                self.clipboard.set_html(html.as_str(), text.as_deref())
+            }
+            ClipboardContent::Image {
+                width,
+                height,
+                rgba,
+            } => self.clipboard.set_image(arboard::ImageData {
+                width: *width,
+                height: *height,
+                bytes: rgba.as_slice().into(),
+            }),
+        };
+        result.map_err(|e| e.to_string())
+    }
+}
+
+// 进程内的剪贴板：没有系统剪贴板时使用，也可以在无图形环境下驱动 ClipboardService
+// 行为与系统剪贴板一致：HTML 可以按纯文本读取，同一时间只保存一份内容
+#[derive(Default)]
+pub struct MemoryClipboard {
+    content: Option<ClipboardContent>,
+}
+
+impl ClipboardBackend for MemoryClipboard {
+    fn read(&mut self, kind: ClipboardKind) -> Result<Option<ClipboardContent>, String> {
+        let Some(content) = &self.content else {
+            return Ok(None);
+        };
+        Ok(match (kind, content) {
+            (ClipboardKind::Text, ClipboardContent::Html { .. }) => content
+                .plain_text()
+                .map(|text| ClipboardContent::Text { text }),
+            _ if content.kind() == kind => Some(content.clone()),
+            _ => None,
+        })
+    }
+
+    fn write(&mut self, content: &ClipboardContent) -> Result<(), String> {
+        self.content = Some(content.clone());
+        Ok(())
+    }
+}
+
+// 一条剪贴板历史；列表中只返回预览，完整内容在粘贴时取出
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardEntry {
+    pub id: u64,
+    pub kind: ClipboardKind,
+    // 文字内容（折叠空白后截断），图片为 "宽x高"
+    pub preview: String,
+    // 最近一次复制的 Unix 时间戳（秒）
+    pub timestamp: u64,
+    #[serde(skip)]
+    content: ClipboardContent,
+}
+
+struct ClipboardHistory {
+    next_id: u64,
+    limit: usize,
+    // 最近复制的排在最前面
+    entries: VecDeque<ClipboardEntry>,
+}
+
+pub struct ClipboardService {
+    backend: Mutex<Box<dyn ClipboardBackend>>,
+    history: Mutex<ClipboardHistory>,
+}
+
+impl ClipboardService {
+    pub fn new(backend: Box<dyn ClipboardBackend>) -> Self {
+        ClipboardService {
+            backend: Mutex::new(backend),
+            history: Mutex::new(ClipboardHistory {
+                next_id: 1,
+                limit: DEFAULT_LIMIT,
+                entries: VecDeque::new(),
+            }),
+        }
+    }
+
+    // 使用系统剪贴板，不可用时退回到进程内的剪贴板
+    pub fn system() -> Self {
+        match SystemClipboard::new() {
+            Ok(clipboard) => ClipboardService::new(Box::new(clipboard)),
+            Err(e) => {
+                eprintln!("[RUST] 系统剪贴板不可用，使用应用内剪贴板: {}", e);
+                ClipboardService::new(Box::new(MemoryClipboard::default()))
+            }
+        }
+    }
+
+    // 读取指定类型的内容；只读不记入历史，历史中只有通过 write 复制的内容
+    // （其他应用放进剪贴板的可能是密码等敏感内容，粘贴一次不应该让它一直留在历史中）
+    pub fn read(&self, kind: ClipboardKind) -> Result<Option<ClipboardContent>, CommandError> {
+        self.backend
+            .lock()
+            .unwrap()
+            .read(kind)
+            .map_err(|reason| CommandError::Clipboard { reason })
+    }
+
+    // 读取最适合粘贴的内容：依次尝试 HTML、文本和图片
+    pub fn read_any(&self) -> Result<Option<ClipboardContent>, CommandError> {
+        for kind in [
+            ClipboardKind::Html,
+            ClipboardKind::Text,
+            ClipboardKind::Image,
+        ] {
+            if let Some(content) = self.read(kind)? {
+                return Ok(Some(content));
+            }
+        }
+        Ok(None)
+    }
+
+    // 写入剪贴板并记入历史
+    pub fn write(&self, content: ClipboardContent) -> Result<(), CommandError> {
+        content.check()?;
+        self.backend
+            .lock()
+            .unwrap()
+            .write(&content)
+            .map_err(|reason| CommandError::Clipboard { reason })?;
+        self.remember(&content);
+        Ok(())
+    }
+
+    // 记入历史：与已有条目相同的内容移到最前面（保留原来的 ID），超出上限时丢弃最旧的
+    fn remember(&self, content: &ClipboardContent) {
+        if content.is_empty() {
+            return;
+        }
+        let mut history = self.history.lock().unwrap();
+        let timestamp = crate::current_timestamp();
+        let existing = history
+            .entries
+            .iter()
+            .position(|entry| entry.content == *content);
+        let entry = match existing.and_then(|index| history.entries.remove(index)) {
+            Some(entry) => ClipboardEntry { timestamp, ..entry },
+            None => {
+                let id = history.next_id;
+                history.next_id += 1;
+                ClipboardEntry {
+                    id,
+                    kind: content.kind(),
+                    preview: content.preview(),
+                    timestamp,
+                    content: content.clone(),
+                }
+            }
+        };
+        history.entries.push_front(entry);
+        let limit = history.limit;
+        history.entries.truncate(limit);
+    }
+
+    // 历史列表（最近的在前）；query 按子串匹配文字内容（不区分大小写），为空时返回全部
+    pub fn history(&self, query: &str, limit: Option<usize>) -> Vec<ClipboardEntry> {
+        let query = query.trim().to_lowercase();
+        let history = self.history.lock().unwrap();
+        history
+            .entries
+            .iter()
+            .filter(|entry| {
+                query.is_empty()
+                    || entry
+                        .content
+                        .plain_text()
+                        .is_some_and(|text| text.to_lowercase().contains(&query))
+            })
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    // 把历史中的一条重新放回剪贴板，返回其内容
+    pub fn restore(&self, id: u64) -> Result<ClipboardContent, CommandError> {
+        let content = {
+            let history = self.history.lock().unwrap();
+            history
+                .entries
+                .iter()
+                .find(|entry| entry.id == id)
+                .map(|entry| entry.content.clone())
+                .ok_or_else(|| CommandError::NotFound {
+                    resource: Resource::ClipboardEntry,
+                    id: id.to_string(),
+                })?
+        };
+        self.write(content.clone())?;
+        Ok(content)
+    }
+
+    pub fn clear_history(&self) {
+        self.history.lock().unwrap().entries.clear();
+    }
+
+    // 修改保留的历史条数，超出新的上限时立即丢弃最旧的
+    pub fn set_limit(&self, limit: usize) -> Result<(), CommandError> {
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(CommandError::invalid_argument(
+                "limit",
+                limit,
+                format!("1..={}", MAX_LIMIT),
+            ));
+        }
+        let mut history = self.history.lock().unwrap();
+        history.limit = limit;
+        history.entries.truncate(limit);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> ClipboardService {
+        ClipboardService::new(Box::new(MemoryClipboard::default()))
+    }
+
+    fn text(text: &str) -> ClipboardContent {
+        ClipboardContent::Text {
+            text: text.to_string(),
+        }
+    }
+
+    fn previews(service: &ClipboardService, query: &str) -> Vec<String> {
+        service
+            .history(query, None)
+            .into_iter()
+            .map(|entry| entry.preview)
+            .collect()
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let clipboard = service();
+        clipboard.write(text("你好 world")).unwrap();
+        assert_eq!(
+            clipboard.read(ClipboardKind::Text).unwrap(),
+            Some(text("你好 world"))
+        );
+        assert_eq!(clipboard.read(ClipboardKind::Html).unwrap(), None);
+        assert_eq!(clipboard.read(ClipboardKind::Image).unwrap(), None);
+    }
+
+    #[test]
+    fn html_round_trip_and_read_as_text() {
+        let clipboard = service();
+        let html = ClipboardContent::Html {
+            html: "<p>Hello <b>world</b></p>".to_string(),
+            text: None,
+        };
+        clipboard.write(html.clone()).unwrap();
+        assert_eq!(clipboard.read(ClipboardKind::Html).unwrap(), Some(html));
+        // 没有纯文本时去掉标签
+        assert_eq!(
+            clipboard.read(ClipboardKind::Text).unwrap(),
+            Some(text("Hello world"))
+        );
+
+        clipboard
+            .write(ClipboardContent::Html {
+                html: "<i>x</i>".to_string(),
+                text: Some("plain".to_string()),
+            })
+            .unwrap();
+        assert_eq!(
+            clipboard.read(ClipboardKind::Text).unwrap(),
+            Some(text("plain"))
+        );
+        assert_eq!(
+            clipboard.read_any().unwrap().map(|content| content.kind()),
+            Some(ClipboardKind::Html)
+        );
+    }
+
+    #[test]
+    fn image_round_trip() {
+        let clipboard = service();
+        let image = ClipboardContent::Image {
+            width: 2,
+            height: 1,
+            rgba: vec![255, 0, 0, 255, 0, 255, 0, 255],
+        };
+        clipboard.write(image.clone()).unwrap();
+        assert_eq!(clipboard.read(ClipboardKind::Image).unwrap(), Some(image));
+        assert_eq!(clipboard.read(ClipboardKind::Text).unwrap(), None);
+        assert_eq!(previews(&clipboard, ""), ["2x1"]);
+    }
+
+    #[test]
+    fn image_size_is_checked() {
+        let clipboard = service();
+        let short = ClipboardContent::Image {
+            width: 2,
+            height: 2,
+            rgba: vec![0; 15],
+        };
+        assert!(matches!(
+            clipboard.write(short),
+            Err(CommandError::InvalidArgument { name, .. }) if name == "rgba"
+        ));
+        let overflow = ClipboardContent::Image {
+            width: usize::MAX,
+            height: 2,
+            rgba: Vec::new(),
+        };
+        assert!(matches!(
+            clipboard.write(overflow),
+            Err(CommandError::InvalidArgument { name, .. }) if name == "height"
+        ));
+        assert!(clipboard.history("", None).is_empty());
+    }
+
+    #[test]
+    fn history_moves_duplicates_to_front() {
+        let clipboard = service();
+        clipboard.write(text("a")).unwrap();
+        clipboard.write(text("b")).unwrap();
+        let first = clipboard.history("", None)[1].id;
+        clipboard.write(text("a")).unwrap();
+
+        let history = clipboard.history("", None);
+        assert_eq!(previews(&clipboard, ""), ["a", "b"]);
+        assert_eq!(history[0].id, first);
+        // 空内容不记入历史
+        clipboard.write(text("")).unwrap();
+        assert_eq!(clipboard.history("", None).len(), 2);
+    }
+
+    #[test]
+    fn reads_are_not_recorded() {
+        let clipboard = service();
+        clipboard.write(text("copied")).unwrap();
+        // 模拟其他应用复制的内容
+        clipboard
+            .backend
+            .lock()
+            .unwrap()
+            .write(&ClipboardContent::Html {
+                html: "<b>secret</b>".to_string(),
+                text: None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            clipboard.read(ClipboardKind::Text).unwrap(),
+            Some(text("secret"))
+        );
+        // 依次尝试各种类型时也不记录
+        assert!(clipboard.read_any().unwrap().is_some());
+        assert_eq!(previews(&clipboard, ""), ["copied"]);
+    }
+
+    #[test]
+    fn history_limit_evicts_oldest() {
+        let clipboard = service();
+        for i in 0..DEFAULT_LIMIT + 5 {
+            clipboard.write(text(&i.to_string())).unwrap();
+        }
+        let history = clipboard.history("", None);
+        assert_eq!(history.len(), DEFAULT_LIMIT);
+        assert_eq!(history[0].preview, (DEFAULT_LIMIT + 4).to_string());
+        assert_eq!(history[DEFAULT_LIMIT - 1].preview, "5");
+
+        clipboard.set_limit(2).unwrap();
+        assert_eq!(
+            previews(&clipboard, ""),
+            [
+                (DEFAULT_LIMIT + 4).to_string(),
+                (DEFAULT_LIMIT + 3).to_string()
+            ]
+        );
+        clipboard.write(text("new")).unwrap();
+        assert_eq!(
+            previews(&clipboard, ""),
+            ["new".to_string(), (DEFAULT_LIMIT + 4).to_string()]
+        );
+
+        assert!(clipboard.set_limit(0).is_err());
+        assert!(clipboard.set_limit(MAX_LIMIT + 1).is_err());
+        assert_eq!(clipboard.history("", Some(1)).len(), 1);
+    }
+
+    #[test]
+    fn history_search() {
+        let clipboard = service();
+        clipboard.write(text("Hello World")).unwrap();
+        clipboard
+            .write(ClipboardContent::Html {
+                html: "<b>hello</b> html".to_string(),
+                text: None,
+            })
+            .unwrap();
+        clipboard.write(text("other")).unwrap();
+        clipboard
+            .write(ClipboardContent::Image {
+                width: 1,
+                height: 1,
+                rgba: vec![0; 4],
+            })
+            .unwrap();
+
+        assert_eq!(
+            previews(&clipboard, "  HELLO "),
+            ["hello html", "Hello World"]
+        );
+        // 搜索只匹配文字，图片的尺寸不参与匹配
+        assert!(clipboard.history("1x1", None).is_empty());
+        assert_eq!(clipboard.history("", None).len(), 4);
+    }
+
+    #[test]
+    fn restore_puts_entry_back() {
+        let clipboard = service();
+        clipboard.write(text("first")).unwrap();
+        clipboard.write(text("second")).unwrap();
+        let first = clipboard.history("first", None)[0].id;
+
+        assert_eq!(clipboard.restore(first).unwrap(), text("first"));
+        assert_eq!(
+            clipboard.read(ClipboardKind::Text).unwrap(),
+            Some(text("first"))
+        );
+        assert_eq!(previews(&clipboard, ""), ["first", "second"]);
+        assert_eq!(clipboard.history("", None)[0].id, first);
+
+        clipboard.clear_history();
+        assert!(matches!(
+            clipboard.restore(first),
+            Err(CommandError::NotFound {
+                resource: Resource::ClipboardEntry,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,1459 @@
+// 文档模型：编辑器中的文本由 Rust 保存，前端把每次修改（插入 / 删除 / 替换）发过来，
+// 每次修改记入撤销历史；连续输入或连续删除的单个字符在短时间内合并为一步撤销
+// 位置和长度按 UTF-16 代码单元计算，与 JavaScript 字符串和 textarea 的 selectionStart 一致
+// Documents 管理所有打开的文档：打开、保存、另存为和关闭，每个文档记录对应的文件、编码和换行符风格
+use encoding_rs::{Encoding, UTF_8};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::atomic_write;
+use crate::error::{CommandError, IoOperation, Resource};
+use crate::text_encoding::{self, Unrepresentable};
+
+// 默认的撤销步数，以及允许设置的最大步数
+const DEFAULT_DEPTH: usize = 100;
+const MAX_DEPTH: usize = 10_000;
+// 保存时最多保留的备份个数
+const MAX_BACKUPS: usize = 20;
+// 自动保存的默认间隔和允许设置的范围（秒），0 表示关闭定时自动保存
+const DEFAULT_AUTOSAVE_SECONDS: u64 = 30;
+const AUTOSAVE_SECONDS: std::ops::RangeInclusive<u64> = 5..=3600;
+// 间隔不超过这个时间的连续输入合并为一步
+const COALESCE_WINDOW: Duration = Duration::from_millis(1000);
+
+// 前端发来的一次修改
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum EditOperation {
+    Insert {
+        offset: usize,
+        text: String,
+    },
+    Delete {
+        offset: usize,
+        length: usize,
+    },
+    Replace {
+        offset: usize,
+        length: usize,
+        text: String,
+    },
+}
+
+// 已经生效的修改：在 offset 处把 removed 换成 inserted；反过来应用即可撤销
+// 也作为增量发给前端，前端可以据此只更新变化的部分
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextChange {
+    pub offset: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+impl TextChange {
+    fn inverse(&self) -> TextChange {
+        TextChange {
+            offset: self.offset,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+        }
+    }
+}
+
+// 换行符风格：文档内部（和 textarea 一样）统一使用 "\n"，保存时转换为文档的风格
+// 打开文件时取文件中占多数的风格；单独的 "\r"（旧的 Mac 风格）读入时也当作换行，但不作为保存的风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+// 文件中各种换行符的个数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineEndingCounts {
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+}
+
+impl LineEndingCounts {
+    pub fn count(text: &str) -> Self {
+        let mut counts = LineEndingCounts::default();
+        let mut bytes = text.bytes().peekable();
+        while let Some(b) = bytes.next() {
+            match b {
+                b'\r' if bytes.peek() == Some(&b'\n') => {
+                    bytes.next();
+                    counts.crlf += 1;
+                }
+                b'\r' => counts.cr += 1,
+                b'\n' => counts.lf += 1,
+                _ => {}
+            }
+        }
+        counts
+    }
+
+    // 占多数的风格；个数相同或没有换行符时为 "\n"
+    pub fn dominant(&self) -> LineEnding {
+        if self.crlf > self.lf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    // 混用了不止一种换行符
+    pub fn is_mixed(&self) -> bool {
+        [self.lf, self.crlf, self.cr]
+            .iter()
+            .filter(|&&n| n > 0)
+            .count()
+            > 1
+    }
+}
+
+// 编码转换的结果中最多列出的无法表示的字符个数
+const MAX_UNREPRESENTABLE: usize = 100;
+
+// 撤销历史中的一步的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepKind {
+    // 输入单个字符
+    Typing,
+    // 删除单个字符（退格或向后删除）
+    Deleting,
+    // 其他修改，不与相邻的修改合并
+    Other,
+}
+
+struct UndoStep {
+    id: u64,
+    kind: StepKind,
+    changes: Vec<TextChange>,
+    // 转换换行符风格的一步：转换前和转换后的风格
+    line_ending: Option<(LineEnding, LineEnding)>,
+    at: Instant,
+}
+
+// UTF-16 长度
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+// UTF-16 下标对应的字节下标；超出文本末尾或落在代理对中间时为 None
+fn byte_index(text: &str, offset: usize) -> Option<usize> {
+    let mut units = 0;
+    for (index, c) in text.char_indices() {
+        if units == offset {
+            return Some(index);
+        }
+        if units > offset {
+            return None;
+        }
+        units += c.len_utf16();
+    }
+    (units == offset).then_some(text.len())
+}
+
+// 准备保存时生成的内容，写文件时不需要再访问文档（见 Documents::save）
+pub struct PendingSave {
+    path: PathBuf,
+    bytes: Vec<u8>,
+    // 准备保存时的状态 ID、编码和 BOM，写入成功后记为保存点
+    state: u64,
+    format: (&'static Encoding, bool),
+}
+
+pub struct Document {
+    text: String,
+    // 对应的文件，新建的文档在第一次保存之前为 None
+    path: Option<PathBuf>,
+    // 文件的编码（打开时检测，见 text_encoding.rs），保存时按这个编码写回
+    encoding: &'static Encoding,
+    // 文件开头是否有 BOM，保存时保留
+    bom: bool,
+    // 打开时有不符合编码的字节被替换成了 U+FFFD
+    malformed: bool,
+    // 上次保存时的编码和 BOM；转换编码后即使文本没变，文档也有未保存的修改
+    saved_format: (&'static Encoding, bool),
+    line_ending: LineEnding,
+    // 文件混用了几种换行符时为各自的个数，保存后文件中只有一种，随之清除
+    mixed_line_endings: Option<LineEndingCounts>,
+    // 每次修改、撤销和重做后加一，前端可以据此丢弃过期的结果
+    version: u64,
+    undo: VecDeque<UndoStep>,
+    redo: Vec<UndoStep>,
+    depth: usize,
+    next_step: u64,
+    // 撤销栈为空时文档状态的 ID；最旧的一步因超出步数被丢弃后变为那一步的 ID
+    base: u64,
+    // 上次保存时的状态 ID；那个状态被撤销后又被新的修改覆盖时，文档再也回不到未修改状态
+    saved: u64,
+    // 保存、撤销或重做之后，下一次输入另起一步
+    coalesce: bool,
+    // 编辑器中选中的范围及报告选区时的文档版本，剪切 / 复制菜单项作用于这个范围；
+    // 文本变化后版本不同，旧的选区随之失效
+    selection: Option<(u64, usize, usize)>,
+}
+
+impl Document {
+    pub fn new(text: String, depth: usize) -> Self {
+        Document {
+            text,
+            path: None,
+            encoding: UTF_8,
+            bom: false,
+            malformed: false,
+            saved_format: (UTF_8, false),
+            line_ending: LineEnding::default(),
+            mixed_line_endings: None,
+            version: 0,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            depth,
+            next_step: 1,
+            base: 0,
+            saved: 0,
+            coalesce: false,
+            selection: None,
+        }
+    }
+
+    // 读取文件：检测编码并解码（去掉 BOM），换行符统一为 "\n"，记下占多数的换行符风格
+    pub fn open(path: &Path, depth: usize) -> Result<Self, CommandError> {
+        let bytes = fs::read(path).map_err(|e| CommandError::io(IoOperation::Read, path, e))?;
+        let decoded = text_encoding::decode(&bytes);
+        if decoded.malformed {
+            eprintln!(
+                "[RUST] {} 中有不符合 {} 编码的字节，已替换为 U+FFFD",
+                path.display(),
+                decoded.encoding.name()
+            );
+        }
+        let counts = LineEndingCounts::count(&decoded.text);
+        if counts.is_mixed() {
+            eprintln!("[RUST] {} 混用了多种换行符: {:?}", path.display(), counts);
+        }
+        let text = decoded.text.replace("\r\n", "\n").replace('\r', "\n");
+
+        let mut document = Document::new(text, depth);
+        document.path = Some(path.to_path_buf());
+        document.encoding = decoded.encoding;
+        document.bom = decoded.bom;
+        document.malformed = decoded.malformed;
+        document.saved_format = (decoded.encoding, decoded.bom);
+        document.line_ending = counts.dominant();
+        document.mixed_line_endings = counts.is_mixed().then_some(counts);
+        Ok(document)
+    }
+
+    // 按文件原来的编码、BOM 和换行符风格生成要保存的内容
+    // path 为 None 时保存到文档对应的文件
+    pub fn prepare_save(&mut self, path: Option<PathBuf>) -> Result<PendingSave, CommandError> {
+        let path = path
+            .or_else(|| self.path.clone())
+            .ok_or(CommandError::PathRequired)?;
+        let content = self.text.replace('\n', self.line_ending.as_str());
+        let bytes = text_encoding::encode(&content, self.encoding, self.bom)?;
+        // 之后的输入不能并入要记为保存点的那一步
+        self.coalesce = false;
+        Ok(PendingSave {
+            path,
+            bytes,
+            state: self.state_id(),
+            format: (self.encoding, self.bom),
+        })
+    }
+
+    // 内容已经写入文件后记录路径和保存点；写文件期间又有新的修改时，那些修改仍然算未保存
+    pub fn finish_save(&mut self, pending: PendingSave) {
+        self.path = Some(pending.path);
+        self.mixed_line_endings = None;
+        self.saved = pending.state;
+        self.saved_format = pending.format;
+        self.coalesce = false;
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // 当前状态的 ID：撤销栈顶那一步的 ID
+    fn state_id(&self) -> u64 {
+        self.undo.back().map(|step| step.id).unwrap_or(self.base)
+    }
+
+    // 与上次保存的内容或编码不同（撤销回保存时的状态后又变为未修改）
+    pub fn is_dirty(&self) -> bool {
+        self.saved != self.state_id() || (self.encoding, self.bom) != self.saved_format
+    }
+
+    // 记录保存点
+    pub fn mark_saved(&mut self) {
+        self.saved = self.state_id();
+        self.saved_format = (self.encoding, self.bom);
+        self.coalesce = false;
+    }
+
+    // 改为以 encoding 保存，文本本身不变；返回文本中 encoding 无法表示的字符
+    // bom 省略时 UTF-16 写入 BOM，编码不变时保留原来的设置，其他情况不写；非 Unicode 编码没有 BOM
+    pub fn set_encoding(
+        &mut self,
+        encoding: &'static Encoding,
+        bom: Option<bool>,
+    ) -> Vec<Unrepresentable> {
+        let unicode = encoding == UTF_8 || text_encoding::is_utf16(encoding);
+        let bom = bom.unwrap_or(if encoding == self.encoding {
+            self.bom
+        } else {
+            text_encoding::is_utf16(encoding)
+        });
+        self.encoding = encoding;
+        self.bom = unicode && bom;
+        self.version += 1;
+        text_encoding::unrepresentable(&self.text, encoding)
+    }
+
+    // 修改撤销步数，超出时丢弃最旧的步骤
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        while self.undo.len() > depth {
+            if let Some(step) = self.undo.pop_front() {
+                self.base = step.id;
+            }
+        }
+    }
+
+    // 应用一次修改但不记入历史（撤销、重做时使用）
+    fn apply_change(&mut self, change: &TextChange) -> Result<(), CommandError> {
+        let start = byte_index(&self.text, change.offset).ok_or_else(|| {
+            CommandError::invalid_argument(
+                "offset",
+                change.offset,
+                format!("0..={}", utf16_len(&self.text)),
+            )
+        })?;
+        let end = start + change.removed.len();
+        if self.text.get(start..end) != Some(change.removed.as_str()) {
+            return Err(CommandError::Internal {
+                reason: format!("撤销历史与文档内容不一致（位置 {}）", change.offset),
+            });
+        }
+        self.text.replace_range(start..end, &change.inserted);
+        Ok(())
+    }
+
+    // 记录编辑器中选中的范围 start..end（UTF-16 位置）
+    pub fn set_selection(&mut self, start: usize, end: usize) -> Result<(), CommandError> {
+        let total = utf16_len(&self.text);
+        if byte_index(&self.text, start).is_none() {
+            return Err(CommandError::invalid_argument(
+                "start",
+                start,
+                format!("0..={}", total),
+            ));
+        }
+        if end < start || byte_index(&self.text, end).is_none() {
+            return Err(CommandError::invalid_argument(
+                "end",
+                end,
+                format!("{}..={}", start, total),
+            ));
+        }
+        self.selection = Some((self.version, start, end));
+        Ok(())
+    }
+
+    // 当前有效的选区；没有选中文字或选区已经过期时为 None
+    fn selection(&self) -> Option<(usize, usize)> {
+        self.selection
+            .filter(|&(version, start, end)| version == self.version && start < end)
+            .map(|(_, start, end)| (start, end))
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        let start = byte_index(&self.text, start)?;
+        let end = byte_index(&self.text, end)?;
+        Some(self.text[start..end].to_string())
+    }
+
+    // 删除选中的文字（剪切），作为单独的一步撤销；没有有效的选区时什么也不做
+    pub fn delete_selection(&mut self) -> Result<Vec<TextChange>, CommandError> {
+        let Some((start, end)) = self.selection() else {
+            return Ok(Vec::new());
+        };
+        let operation = EditOperation::Delete {
+            offset: start,
+            length: end - start,
+        };
+        self.apply_step(&[operation], None)
+    }
+
+    // 把前端发来的修改转换为 TextChange：检查位置和长度，取出被删除的文字
+    fn resolve(&self, operation: &EditOperation) -> Result<TextChange, CommandError> {
+        let (offset, length, text) = match operation {
+            EditOperation::Insert { offset, text } => (*offset, 0, text.as_str()),
+            EditOperation::Delete { offset, length } => (*offset, *length, ""),
+            EditOperation::Replace {
+                offset,
+                length,
+                text,
+            } => (*offset, *length, text.as_str()),
+        };
+        let total = utf16_len(&self.text);
+        let start = byte_index(&self.text, offset).ok_or_else(|| {
+            CommandError::invalid_argument("offset", offset, format!("0..={}", total))
+        })?;
+        let end = offset
+            .checked_add(length)
+            .and_then(|end| byte_index(&self.text, end))
+            .ok_or_else(|| {
+                CommandError::invalid_argument(
+                    "length",
+                    length,
+                    format!("0..={}", total.saturating_sub(offset)),
+                )
+            })?;
+        Ok(TextChange {
+            offset,
+            removed: self.text[start..end].to_string(),
+            inserted: text.to_string(),
+        })
+    }
+
+    // 应用一组修改（每处修改的位置都相对于前一处修改之后的文本），作为一步记入撤销历史
+    // 任何一处修改无效时整组都不生效
+    pub fn edit(&mut self, operations: &[EditOperation]) -> Result<Vec<TextChange>, CommandError> {
+        let changes = self.apply_operations(operations)?;
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+
+        let kind = match changes.as_slice() {
+            [change] if change.removed.is_empty() && change.inserted.chars().count() == 1 => {
+                StepKind::Typing
+            }
+            [change] if change.inserted.is_empty() && change.removed.chars().count() == 1 => {
+                StepKind::Deleting
+            }
+            _ => StepKind::Other,
+        };
+        self.record(kind, changes.clone(), None);
+        self.version += 1;
+        Ok(changes)
+    }
+
+    // 依次应用修改但不记入历史，返回实际改变了文本的修改；任何一处修改无效时整组都不生效
+    fn apply_operations(
+        &mut self,
+        operations: &[EditOperation],
+    ) -> Result<Vec<TextChange>, CommandError> {
+        let original = self.text.clone();
+        let mut changes = Vec::with_capacity(operations.len());
+        for operation in operations {
+            let change = match self.resolve(operation) {
+                Ok(change) => change,
+                Err(e) => {
+                    self.text = original;
+                    return Err(e);
+                }
+            };
+            // resolve 已经检查过位置，这里不会失败
+            self.apply_change(&change)?;
+            changes.push(change);
+        }
+        changes.retain(|change| change.removed != change.inserted);
+        Ok(changes)
+    }
+
+    // 整理文档（转换换行符、删除行尾空白、补上末尾换行）和剪切：作为单独的一步记入撤销历史，
+    // 不与前后的输入合并；line_ending 为转换前后的换行符风格
+    fn apply_step(
+        &mut self,
+        operations: &[EditOperation],
+        line_ending: Option<(LineEnding, LineEnding)>,
+    ) -> Result<Vec<TextChange>, CommandError> {
+        let changes = self.apply_operations(operations)?;
+        if changes.is_empty() && line_ending.is_none() {
+            return Ok(changes);
+        }
+        if let Some((_, after)) = line_ending {
+            self.line_ending = after;
+        }
+        self.record(StepKind::Other, changes.clone(), line_ending);
+        self.version += 1;
+        Ok(changes)
+    }
+
+    // 改为以 line_ending 风格保存；文本本身不变，所以返回的修改总是为空，撤销即恢复原来的风格
+    // 混用换行符的文件即使选择了当前的风格也算一次修改：保存后文件中只剩这一种换行符
+    pub fn convert_line_ending(
+        &mut self,
+        line_ending: LineEnding,
+    ) -> Result<Vec<TextChange>, CommandError> {
+        if line_ending == self.line_ending && self.mixed_line_endings.is_none() {
+            return Ok(Vec::new());
+        }
+        self.mixed_line_endings = None;
+        self.apply_step(&[], Some((self.line_ending, line_ending)))
+    }
+
+    // 删除每行末尾的空白字符（空格、制表符、全角空格等）
+    pub fn trim_trailing_whitespace(&mut self) -> Result<Vec<TextChange>, CommandError> {
+        // 从后往前删除，前面各行的位置不受影响
+        let mut operations = Vec::new();
+        let mut offset = 0;
+        for line in self.text.split('\n') {
+            let trimmed = line.trim_end_matches(|c: char| c.is_whitespace());
+            let length = utf16_len(line);
+            let kept = utf16_len(trimmed);
+            if kept < length {
+                operations.push(EditOperation::Delete {
+                    offset: offset + kept,
+                    length: length - kept,
+                });
+            }
+            offset += length + 1;
+        }
+        operations.reverse();
+        self.apply_step(&operations, None)
+    }
+
+    // 文档不为空且不以换行结尾时在末尾补上换行
+    pub fn ensure_final_newline(&mut self) -> Result<Vec<TextChange>, CommandError> {
+        if self.text.is_empty() || self.text.ends_with('\n') {
+            return Ok(Vec::new());
+        }
+        let operation = EditOperation::Insert {
+            offset: utf16_len(&self.text),
+            text: "\n".to_string(),
+        };
+        self.apply_step(&[operation], None)
+    }
+
+    fn record(
+        &mut self,
+        kind: StepKind,
+        changes: Vec<TextChange>,
+        line_ending: Option<(LineEnding, LineEnding)>,
+    ) {
+        let now = Instant::now();
+        self.redo.clear();
+        if self.coalesce && self.try_merge(kind, &changes, now) {
+            return;
+        }
+
+        let id = self.next_step;
+        self.next_step += 1;
+        self.undo.push_back(UndoStep {
+            id,
+            kind,
+            changes,
+            line_ending,
+            at: now,
+        });
+        self.coalesce = kind != StepKind::Other;
+        let depth = self.depth;
+        self.set_depth(depth);
+    }
+
+    // 把单个字符的输入 / 删除并入上一步；保存点所在的那一步不能再修改
+    fn try_merge(&mut self, kind: StepKind, changes: &[TextChange], now: Instant) -> bool {
+        let saved = self.saved;
+        let Some(last) = self.undo.back_mut() else {
+            return false;
+        };
+        if last.kind != kind || saved == last.id || now - last.at > COALESCE_WINDOW {
+            return false;
+        }
+        let (Some(previous), [change]) = (last.changes.last_mut(), changes) else {
+            return false;
+        };
+        match kind {
+            // 紧接在上次输入之后，换行另起一步
+            StepKind::Typing => {
+                if previous.inserted.ends_with('\n')
+                    || change.offset != previous.offset + utf16_len(&previous.inserted)
+                {
+                    return false;
+                }
+                previous.inserted.push_str(&change.inserted);
+            }
+            StepKind::Deleting => {
+                if change.offset + utf16_len(&change.removed) == previous.offset {
+                    // 退格
+                    previous.offset = change.offset;
+                    previous.removed.insert_str(0, &change.removed);
+                } else if change.offset == previous.offset {
+                    // 向后删除
+                    previous.removed.push_str(&change.removed);
+                } else {
+                    return false;
+                }
+            }
+            StepKind::Other => return false,
+        }
+        last.at = now;
+        true
+    }
+
+    // 撤销一步，返回为此对文本所做的修改；没有可撤销的步骤时返回 None
+    pub fn undo(&mut self) -> Result<Option<Vec<TextChange>>, CommandError> {
+        let Some(step) = self.undo.pop_back() else {
+            return Ok(None);
+        };
+        let changes: Vec<TextChange> = step.changes.iter().rev().map(TextChange::inverse).collect();
+        for change in &changes {
+            self.apply_change(change)?;
+        }
+        if let Some((before, _)) = step.line_ending {
+            self.line_ending = before;
+        }
+        self.redo.push(step);
+        self.version += 1;
+        self.coalesce = false;
+        Ok(Some(changes))
+    }
+
+    pub fn redo(&mut self) -> Result<Option<Vec<TextChange>>, CommandError> {
+        let Some(step) = self.redo.pop() else {
+            return Ok(None);
+        };
+        for change in &step.changes {
+            self.apply_change(change)?;
+        }
+        if let Some((_, after)) = step.line_ending {
+            self.line_ending = after;
+        }
+        let changes = step.changes.clone();
+        self.undo.push_back(step);
+        self.version += 1;
+        self.coalesce = false;
+        Ok(Some(changes))
+    }
+}
+
+// 文档的概况，撤销 / 重做菜单项的启用状态根据 canUndo / canRedo 设置
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentInfo {
+    pub id: u64,
+    pub path: Option<PathBuf>,
+    // 编码名称，例如 "UTF-8"、"GBK"、"Shift_JIS"、"UTF-16LE"
+    pub encoding: String,
+    pub bom: bool,
+    // 打开时有无法解码的字节（已替换为 U+FFFD），按原编码保存会丢失这些字节
+    pub malformed: bool,
+    pub line_ending: LineEnding,
+    // 文件混用了多种换行符时为各自的个数（保存时统一为 lineEnding），否则为 null
+    pub mixed_line_endings: Option<LineEndingCounts>,
+    pub version: u64,
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub dirty: bool,
+}
+
+// 文档的概况和全文，也是 "document-opened" 事件的载荷
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentState {
+    #[serde(flatten)]
+    pub info: DocumentInfo,
+    pub text: String,
+}
+
+// 修改、撤销或重做的结果，也是 "document-changed" 事件的载荷
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentChange {
+    #[serde(flatten)]
+    pub document: DocumentState,
+    pub changes: Vec<TextChange>,
+}
+
+// 转换编码的结果：转换后的文档和新编码无法表示的字符（最多列出前 100 个），
+// 这些字符需要删除或替换后才能保存
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodingConversion {
+    #[serde(flatten)]
+    pub document: DocumentInfo,
+    pub unrepresentable: Vec<Unrepresentable>,
+    pub unrepresentable_count: usize,
+}
+
+// 文档相关的设置，保存在应用配置目录下
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSettings {
+    // 每个文档的撤销步数
+    #[serde(default = "default_depth")]
+    pub undo_depth: usize,
+    // 保存时保留的备份个数（file.txt.bak、file.txt.bak.2 ……），0 表示不备份
+    #[serde(default)]
+    pub backups: usize,
+    // 把未保存的修改写入恢复目录的间隔（秒），见 autosave.rs
+    #[serde(default = "default_autosave_seconds")]
+    pub autosave_seconds: u64,
+}
+
+impl Default for DocumentSettings {
+    fn default() -> Self {
+        DocumentSettings {
+            undo_depth: DEFAULT_DEPTH,
+            backups: 0,
+            autosave_seconds: DEFAULT_AUTOSAVE_SECONDS,
+        }
+    }
+}
+
+fn default_depth() -> usize {
+    DEFAULT_DEPTH
+}
+
+fn default_autosave_seconds() -> u64 {
+    DEFAULT_AUTOSAVE_SECONDS
+}
+
+struct DocumentsInner {
+    next_id: u64,
+    settings: DocumentSettings,
+    documents: HashMap<u64, Document>,
+    // 每个窗口当前编辑的文档，撤销 / 重做菜单项作用于这个文档
+    active: HashMap<String, u64>,
+}
+
+// 所有打开的文档
+pub struct Documents {
+    // 设置文件
+    file: PathBuf,
+    inner: Mutex<DocumentsInner>,
+    // 保存期间持有，保证同时发起的保存按准备内容的先后写入文件
+    saving: Mutex<()>,
+}
+
+fn info_of(id: u64, document: &Document) -> DocumentInfo {
+    DocumentInfo {
+        id,
+        path: document.path.clone(),
+        encoding: document.encoding.name().to_string(),
+        bom: document.bom,
+        malformed: document.malformed,
+        line_ending: document.line_ending,
+        mixed_line_endings: document.mixed_line_endings,
+        version: document.version(),
+        can_undo: document.can_undo(),
+        can_redo: document.can_redo(),
+        dirty: document.is_dirty(),
+    }
+}
+
+fn state_of(id: u64, document: &Document) -> DocumentState {
+    DocumentState {
+        info: info_of(id, document),
+        text: document.text().to_string(),
+    }
+}
+
+// 两个路径是否指向同一个文件（文件不存在等无法规范化时按原样比较）
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn document_not_found(id: u64) -> CommandError {
+    CommandError::NotFound {
+        resource: Resource::Document,
+        id: id.to_string(),
+    }
+}
+
+impl Documents {
+    // 加载设置；文件不存在或内容损坏时使用默认设置，超出范围的值取最近的边界值
+    pub fn load(file: PathBuf) -> Self {
+        let mut settings = fs::read_to_string(&file)
+            .ok()
+            .and_then(
+                |text| match serde_json::from_str::<DocumentSettings>(&text) {
+                    Ok(settings) => Some(settings),
+                    Err(e) => {
+                        eprintln!("[RUST] 文档设置解析失败，使用默认设置: {:?}", e);
+                        None
+                    }
+                },
+            )
+            .unwrap_or_default();
+        settings.undo_depth = settings.undo_depth.clamp(1, MAX_DEPTH);
+        settings.backups = settings.backups.min(MAX_BACKUPS);
+        if settings.autosave_seconds != 0 {
+            settings.autosave_seconds = settings
+                .autosave_seconds
+                .clamp(*AUTOSAVE_SECONDS.start(), *AUTOSAVE_SECONDS.end());
+        }
+
+        Documents {
+            file,
+            inner: Mutex::new(DocumentsInner {
+                next_id: 1,
+                settings,
+                documents: HashMap::new(),
+                active: HashMap::new(),
+            }),
+            saving: Mutex::new(()),
+        }
+    }
+
+    fn persist(&self, settings: &DocumentSettings) -> Result<(), CommandError> {
+        let write_error = |e| CommandError::io(IoOperation::Write, &self.file, e);
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir).map_err(write_error)?;
+        }
+        let text = serde_json::to_string_pretty(settings).map_err(|e| CommandError::Internal {
+            reason: e.to_string(),
+        })?;
+        fs::write(&self.file, text).map_err(write_error)
+    }
+
+    pub fn settings(&self) -> DocumentSettings {
+        self.inner.lock().unwrap().settings.clone()
+    }
+
+    // 新建文档，并设为 window 当前编辑的文档
+    pub fn create(&self, window: &str, text: String) -> DocumentState {
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        let document = Document::new(text, inner.settings.undo_depth);
+        let state = state_of(id, &document);
+        inner.documents.insert(id, document);
+        inner.active.insert(window.to_string(), id);
+        state
+    }
+
+    // 打开文件并设为 window 当前编辑的文档；文件已经打开时直接切换到那个文档
+    pub fn open(&self, window: &str, path: &Path) -> Result<DocumentState, CommandError> {
+        let mut inner = self.inner.lock().unwrap();
+        let existing = inner
+            .documents
+            .iter()
+            .find(|(_, document)| document.path().is_some_and(|open| same_file(open, path)))
+            .map(|(id, _)| *id);
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                let document = Document::open(path, inner.settings.undo_depth)?;
+                let id = inner.next_id;
+                inner.next_id += 1;
+                inner.documents.insert(id, document);
+                id
+            }
+        };
+        inner.active.insert(window.to_string(), id);
+        Ok(state_of(id, &inner.documents[&id]))
+    }
+
+    // 从恢复快照还原文档：path 指向的文件存在时打开它（已打开时沿用那个文档），否则新建文档并记下 path；
+    // 再把全文替换为快照中的内容，作为一步可以撤销的修改，所以还原后的文档处于未保存状态
+    // format 为快照记录的编码和 BOM，旧的快照中没有时沿用打开文件时检测到的编码
+    pub fn recover(
+        &self,
+        window: &str,
+        path: Option<&Path>,
+        format: Option<(&'static Encoding, bool)>,
+        line_ending: LineEnding,
+        text: String,
+    ) -> Result<DocumentState, CommandError> {
+        let id = match path.filter(|path| path.exists()) {
+            Some(path) => self.open(window, path)?.info.id,
+            None => {
+                let id = self.create(window, String::new()).info.id;
+                self.with(id, |document| {
+                    document.path = path.map(Path::to_path_buf);
+                    document.line_ending = line_ending;
+                    Ok(())
+                })?;
+                id
+            }
+        };
+        self.with(id, |document| {
+            if let Some((encoding, bom)) = format {
+                document.set_encoding(encoding, Some(bom));
+            }
+            if document.text() != text {
+                let length = document.text().encode_utf16().count();
+                document.edit(&[EditOperation::Replace {
+                    offset: 0,
+                    length,
+                    text,
+                }])?;
+            }
+            Ok(state_of(id, document))
+        })
+    }
+
+    // 保存文档，path 为 None 时保存到文档对应的文件（另存为时给出新路径）
+    // 只在生成内容和记录保存点时持有文档锁，原子写入文件（见 atomic_write.rs）期间
+    // 其他文档的编辑和查询不会被阻塞
+    pub fn save(&self, id: u64, path: Option<PathBuf>) -> Result<DocumentInfo, CommandError> {
+        let backups = self.settings().backups;
+        let _saving = self.saving.lock().unwrap();
+        let pending = self.with(id, |document| document.prepare_save(path))?;
+        atomic_write::write_file(&pending.path, &pending.bytes, backups)?;
+        self.with(id, |document| {
+            document.finish_save(pending);
+            Ok(info_of(id, document))
+        })
+    }
+
+    // 关闭文档；有未保存的修改时返回 UnsavedChanges，除非 discard 为 true
+    pub fn close(&self, id: u64, discard: bool) -> Result<(), CommandError> {
+        let mut inner = self.inner.lock().unwrap();
+        let document = inner
+            .documents
+            .get(&id)
+            .ok_or_else(|| document_not_found(id))?;
+        if document.is_dirty() && !discard {
+            return Err(CommandError::UnsavedChanges {
+                id: id.to_string(),
+                path: document.path().map(|path| path.display().to_string()),
+            });
+        }
+        inner.documents.remove(&id);
+        inner.active.retain(|_, active| *active != id);
+        Ok(())
+    }
+
+    // 把文档转换为以 encoding 保存（见 Document::set_encoding）
+    pub fn convert_encoding(
+        &self,
+        id: u64,
+        encoding: &'static Encoding,
+        bom: Option<bool>,
+    ) -> Result<EncodingConversion, CommandError> {
+        self.with(id, |document| {
+            let mut unrepresentable = document.set_encoding(encoding, bom);
+            let unrepresentable_count = unrepresentable.len();
+            unrepresentable.truncate(MAX_UNREPRESENTABLE);
+            Ok(EncodingConversion {
+                document: info_of(id, document),
+                unrepresentable,
+                unrepresentable_count,
+            })
+        })
+    }
+
+    // 所有打开的文档，按打开的先后排列
+    pub fn list(&self) -> Vec<DocumentInfo> {
+        let inner = self.inner.lock().unwrap();
+        let mut documents: Vec<DocumentInfo> = inner
+            .documents
+            .iter()
+            .map(|(id, document)| info_of(*id, document))
+            .collect();
+        documents.sort_by_key(|info| info.id);
+        documents
+    }
+
+    pub fn info(&self, id: u64) -> Result<DocumentInfo, CommandError> {
+        self.with(id, |document| Ok(info_of(id, document)))
+    }
+
+    // 对文档执行 f；文档不存在时返回 NotFound
+    pub fn with<T>(
+        &self,
+        id: u64,
+        f: impl FnOnce(&mut Document) -> Result<T, CommandError>,
+    ) -> Result<T, CommandError> {
+        let mut inner = self.inner.lock().unwrap();
+        let document = inner
+            .documents
+            .get_mut(&id)
+            .ok_or_else(|| document_not_found(id))?;
+        f(document)
+    }
+
+    pub fn state(&self, id: u64) -> Result<DocumentState, CommandError> {
+        self.with(id, |document| Ok(state_of(id, document)))
+    }
+
+    // 设置 window 当前编辑的文档
+    pub fn activate(&self, window: &str, id: u64) -> Result<(), CommandError> {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.documents.contains_key(&id) {
+            return Err(document_not_found(id));
+        }
+        inner.active.insert(window.to_string(), id);
+        Ok(())
+    }
+
+    // window 当前编辑的文档
+    pub fn active(&self, window: &str) -> Option<u64> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .active
+            .get(window)
+            .copied()
+            .filter(|id| inner.documents.contains_key(id))
+    }
+
+    // window 当前编辑的文档能否撤销、重做；没有文档时都为 false
+    pub fn undo_state(&self, window: &str) -> (bool, bool) {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .active
+            .get(window)
+            .and_then(|id| inner.documents.get(id))
+            .map(|document| (document.can_undo(), document.can_redo()))
+            .unwrap_or((false, false))
+    }
+
+    // 应用修改并记入撤销历史
+    pub fn edit(
+        &self,
+        id: u64,
+        operations: &[EditOperation],
+    ) -> Result<DocumentChange, CommandError> {
+        self.change(id, |document| document.edit(operations))
+    }
+
+    // 整理文档：转换换行符风格、删除行尾空白、补上末尾换行，各自作为一步撤销；
+    // 已经符合要求时什么也不做，changes 为空
+    pub fn convert_line_ending(
+        &self,
+        id: u64,
+        line_ending: LineEnding,
+    ) -> Result<DocumentChange, CommandError> {
+        self.change(id, |document| document.convert_line_ending(line_ending))
+    }
+
+    pub fn trim_trailing_whitespace(&self, id: u64) -> Result<DocumentChange, CommandError> {
+        self.change(id, Document::trim_trailing_whitespace)
+    }
+
+    pub fn ensure_final_newline(&self, id: u64) -> Result<DocumentChange, CommandError> {
+        self.change(id, Document::ensure_final_newline)
+    }
+
+    // 记录 window 中编辑器的选区，并把这个文档设为 window 当前编辑的文档
+    pub fn select(
+        &self,
+        window: &str,
+        id: u64,
+        start: usize,
+        end: usize,
+    ) -> Result<(), CommandError> {
+        self.with(id, |document| document.set_selection(start, end))?;
+        self.activate(window, id)
+    }
+
+    // 选中的文字；没有选中文字时为 None
+    pub fn selected_text(&self, id: u64) -> Result<Option<String>, CommandError> {
+        self.with(id, |document| Ok(document.selected_text()))
+    }
+
+    pub fn delete_selection(&self, id: u64) -> Result<DocumentChange, CommandError> {
+        self.change(id, Document::delete_selection)
+    }
+
+    fn change(
+        &self,
+        id: u64,
+        f: impl FnOnce(&mut Document) -> Result<Vec<TextChange>, CommandError>,
+    ) -> Result<DocumentChange, CommandError> {
+        self.with(id, |document| {
+            let changes = f(document)?;
+            Ok(DocumentChange {
+                document: state_of(id, document),
+                changes,
+            })
+        })
+    }
+
+    // 撤销 / 重做一步；没有可撤销（重做）的步骤时 changes 为空
+    pub fn undo(&self, id: u64) -> Result<DocumentChange, CommandError> {
+        self.change(id, |document| Ok(document.undo()?.unwrap_or_default()))
+    }
+
+    pub fn redo(&self, id: u64) -> Result<DocumentChange, CommandError> {
+        self.change(id, |document| Ok(document.redo()?.unwrap_or_default()))
+    }
+
+    // 修改撤销步数并保存设置，对已打开的文档同样生效
+    pub fn set_depth(&self, depth: usize) -> Result<(), CommandError> {
+        if !(1..=MAX_DEPTH).contains(&depth) {
+            return Err(CommandError::invalid_argument(
+                "depth",
+                depth,
+                format!("1..={}", MAX_DEPTH),
+            ));
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let settings = DocumentSettings {
+            undo_depth: depth,
+            ..inner.settings.clone()
+        };
+        self.persist(&settings)?;
+        inner.settings = settings;
+        for document in inner.documents.values_mut() {
+            document.set_depth(depth);
+        }
+        Ok(())
+    }
+
+    // 修改自动保存的间隔（秒）并保存设置，0 表示关闭
+    pub fn set_autosave_interval(&self, seconds: u64) -> Result<(), CommandError> {
+        if seconds != 0 && !AUTOSAVE_SECONDS.contains(&seconds) {
+            return Err(CommandError::invalid_argument(
+                "seconds",
+                seconds,
+                format!(
+                    "0 | {}..={}",
+                    AUTOSAVE_SECONDS.start(),
+                    AUTOSAVE_SECONDS.end()
+                ),
+            ));
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let settings = DocumentSettings {
+            autosave_seconds: seconds,
+            ..inner.settings.clone()
+        };
+        self.persist(&settings)?;
+        inner.settings = settings;
+        Ok(())
+    }
+
+    // 修改保存时保留的备份个数并保存设置
+    pub fn set_backups(&self, backups: usize) -> Result<(), CommandError> {
+        if backups > MAX_BACKUPS {
+            return Err(CommandError::invalid_argument(
+                "backups",
+                backups,
+                format!("0..={}", MAX_BACKUPS),
+            ));
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let settings = DocumentSettings {
+            backups,
+            ..inner.settings.clone()
+        };
+        self.persist(&settings)?;
+        inner.settings = settings;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(document: &mut Document, offset: usize, text: &str) {
+        document
+            .edit(&[EditOperation::Insert {
+                offset,
+                text: text.to_string(),
+            }])
+            .unwrap();
+    }
+
+    fn delete(document: &mut Document, offset: usize, length: usize) {
+        document
+            .edit(&[EditOperation::Delete { offset, length }])
+            .unwrap();
+    }
+
+    // 把最近一步的时间往前拨，模拟停顿超过合并的时间窗口
+    fn pause(document: &mut Document) {
+        let step = document.undo.back_mut().unwrap();
+        step.at -= COALESCE_WINDOW + Duration::from_millis(1);
+    }
+
+    fn undo_all(document: &mut Document) -> usize {
+        let mut steps = 0;
+        while document.undo().unwrap().is_some() {
+            steps += 1;
+        }
+        steps
+    }
+
+    #[test]
+    fn typing_coalesces_into_one_step() {
+        let mut document = Document::new(String::new(), DEFAULT_DEPTH);
+        for (i, c) in "abc".chars().enumerate() {
+            insert(&mut document, i, &c.to_string());
+        }
+        assert_eq!(document.undo.len(), 1);
+        document.undo().unwrap();
+        assert_eq!(document.text(), "");
+        assert!(!document.can_undo());
+    }
+
+    #[test]
+    fn typing_boundaries_start_new_steps() {
+        let mut document = Document::new(String::new(), DEFAULT_DEPTH);
+        insert(&mut document, 0, "a");
+        insert(&mut document, 1, "b");
+        // 换行之后另起一步
+        insert(&mut document, 2, "\n");
+        insert(&mut document, 3, "c");
+        assert_eq!(document.undo.len(), 2);
+        // 不紧接在上次输入之后
+        insert(&mut document, 0, "x");
+        assert_eq!(document.undo.len(), 3);
+        // 停顿超过时间窗口
+        pause(&mut document);
+        insert(&mut document, 1, "y");
+        assert_eq!(document.undo.len(), 4);
+        // 多个字符（粘贴）不合并，之后的输入也不并入
+        insert(&mut document, 2, "zz");
+        insert(&mut document, 4, "w");
+        assert_eq!(document.undo.len(), 6);
+        // 撤销之后的输入另起一步
+        document.undo().unwrap();
+        insert(&mut document, 4, "v");
+        assert_eq!(document.undo.len(), 6);
+        assert_eq!(document.text(), "xyzzvab\nc");
+
+        assert_eq!(undo_all(&mut document), 6);
+        assert_eq!(document.text(), "");
+    }
+
+    #[test]
+    fn deleting_coalesces_backspace_and_forward_delete() {
+        let mut document = Document::new("abcdef".to_string(), DEFAULT_DEPTH);
+        // 退格：从 f 往前删到 d
+        delete(&mut document, 5, 1);
+        delete(&mut document, 4, 1);
+        delete(&mut document, 3, 1);
+        assert_eq!(document.undo.len(), 1);
+        // 向后删除：在 a 处连续删除
+        pause(&mut document);
+        delete(&mut document, 0, 1);
+        delete(&mut document, 0, 1);
+        assert_eq!(document.undo.len(), 2);
+        assert_eq!(document.text(), "c");
+        // 输入和删除不合并
+        insert(&mut document, 1, "x");
+        delete(&mut document, 1, 1);
+        assert_eq!(document.undo.len(), 4);
+
+        document.undo().unwrap();
+        document.undo().unwrap();
+        document.undo().unwrap();
+        assert_eq!(document.text(), "abc");
+        document.undo().unwrap();
+        assert_eq!(document.text(), "abcdef");
+    }
+
+    #[test]
+    fn save_point_tracks_undo_and_redo() {
+        let mut document = Document::new(String::new(), DEFAULT_DEPTH);
+        assert!(!document.is_dirty());
+        insert(&mut document, 0, "a");
+        assert!(document.is_dirty());
+        document.mark_saved();
+        assert!(!document.is_dirty());
+
+        // 保存后的输入不并入保存点所在的那一步
+        insert(&mut document, 1, "b");
+        assert_eq!(document.undo.len(), 2);
+        assert!(document.is_dirty());
+        document.undo().unwrap();
+        assert!(!document.is_dirty());
+        document.undo().unwrap();
+        assert!(document.is_dirty());
+        document.redo().unwrap();
+        assert!(!document.is_dirty());
+        document.redo().unwrap();
+        assert!(document.is_dirty());
+    }
+
+    #[test]
+    fn new_edit_after_undoing_past_save_point_stays_dirty() {
+        let mut document = Document::new(String::new(), DEFAULT_DEPTH);
+        insert(&mut document, 0, "saved");
+        document.mark_saved();
+        document.undo().unwrap();
+        assert!(document.is_dirty());
+        // 保存时的状态被新的修改覆盖，再也回不去
+        insert(&mut document, 0, "other");
+        assert!(!document.can_redo());
+        assert!(document.is_dirty());
+        document.undo().unwrap();
+        assert!(document.is_dirty());
+        assert_eq!(document.text(), "");
+    }
+
+    #[test]
+    fn edits_made_while_saving_stay_dirty() {
+        let mut document = Document::new(String::new(), DEFAULT_DEPTH);
+        insert(&mut document, 0, "a");
+        let pending = document.prepare_save(Some(PathBuf::from("a.txt"))).unwrap();
+        assert_eq!(pending.bytes, b"a");
+        // 写文件期间文档锁已经释放，又有新的输入
+        insert(&mut document, 1, "b");
+        document.finish_save(pending);
+        assert!(document.is_dirty());
+        document.undo().unwrap();
+        assert!(!document.is_dirty());
+        assert_eq!(document.path, Some(PathBuf::from("a.txt")));
+    }
+
+    #[test]
+    fn save_requires_a_path() {
+        let mut document = Document::new(String::new(), DEFAULT_DEPTH);
+        assert!(matches!(
+            document.prepare_save(None),
+            Err(CommandError::PathRequired)
+        ));
+    }
+
+    #[test]
+    fn normalising_mixed_line_endings_is_an_undoable_change() {
+        let mut document = Document::new("a\nb\nc".to_string(), DEFAULT_DEPTH);
+        document.mixed_line_endings = Some(LineEndingCounts::count("a\r\nb\r\nc\n"));
+        let current = document.line_ending;
+
+        // 选择当前的风格也会记录一步，保存时统一换行符
+        assert!(document.convert_line_ending(current).unwrap().is_empty());
+        assert!(document.mixed_line_endings.is_none());
+        assert!(document.is_dirty());
+        assert_eq!(document.undo.len(), 1);
+
+        // 已经统一之后再选择同样的风格什么也不做
+        document.convert_line_ending(current).unwrap();
+        assert_eq!(document.undo.len(), 1);
+        document.undo().unwrap();
+        assert!(!document.is_dirty());
+        assert_eq!(document.line_ending, current);
+    }
+
+    #[test]
+    fn depth_evicts_oldest_steps() {
+        let mut document = Document::new(String::new(), 3);
+        // 每步都是多个字符，不会合并
+        for i in 0..5 {
+            insert(&mut document, i * 2, "ab");
+        }
+        assert_eq!(document.undo.len(), 3);
+        assert_eq!(undo_all(&mut document), 3);
+        assert_eq!(document.text(), "abab");
+        // 最初的保存点（空文档）已经被丢弃，撤销到底仍有未保存的修改
+        assert!(document.is_dirty());
+
+        // 减小步数立即丢弃多余的步骤
+        let mut document = Document::new(String::new(), 10);
+        for i in 0..5 {
+            insert(&mut document, i * 2, "ab");
+        }
+        document.set_depth(2);
+        assert_eq!(undo_all(&mut document), 2);
+        assert_eq!(document.text(), "ababab");
+    }
+
+    #[test]
+    fn rejects_invalid_offsets_and_lengths() {
+        let mut document = Document::new("a😀b".to_string(), DEFAULT_DEPTH);
+        let invalid = |result: Result<Vec<TextChange>, CommandError>, argument: &str| {
+            assert!(matches!(
+                result,
+                Err(CommandError::InvalidArgument { name, .. }) if name == argument
+            ));
+        };
+
+        // 超出文本末尾（按 UTF-16 计，"a😀b" 的长度为 4）
+        invalid(
+            document.edit(&[EditOperation::Insert {
+                offset: 5,
+                text: "x".to_string(),
+            }]),
+            "offset",
+        );
+        // 落在代理对中间
+        invalid(
+            document.edit(&[EditOperation::Delete {
+                offset: 2,
+                length: 1,
+            }]),
+            "offset",
+        );
+        invalid(
+            document.edit(&[EditOperation::Delete {
+                offset: 1,
+                length: 1,
+            }]),
+            "length",
+        );
+        invalid(
+            document.edit(&[EditOperation::Replace {
+                offset: 3,
+                length: 2,
+                text: String::new(),
+            }]),
+            "length",
+        );
+        invalid(
+            document.edit(&[EditOperation::Delete {
+                offset: 1,
+                length: usize::MAX,
+            }]),
+            "length",
+        );
+        // 一组修改中有一处无效时整组都不生效
+        invalid(
+            document.edit(&[
+                EditOperation::Insert {
+                    offset: 0,
+                    text: "x".to_string(),
+                },
+                EditOperation::Delete {
+                    offset: 10,
+                    length: 1,
+                },
+            ]),
+            "offset",
+        );
+        assert_eq!(document.text(), "a😀b");
+        assert!(!document.can_undo());
+        assert_eq!(document.version(), 0);
+
+        delete(&mut document, 1, 2);
+        assert_eq!(document.text(), "ab");
+    }
+}
@@ -0,0 +1,51 @@
+// 窗口焦点和键盘修饰键状态，用于确定菜单动作的来源窗口和触发方式
+// muda 的菜单事件只带菜单项 ID，无法直接区分点击和快捷键：快捷键都带有修饰键，
+// 所以事件发生时来源窗口中仍按着 Ctrl / Cmd / Alt / Shift 就认为是快捷键触发的
+// 修饰键状态由前端通过 report_modifiers 命令上报（窗口中的 keydown / keyup）
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+#[derive(Default)]
+pub struct FocusTracker {
+    // 最近一次获得焦点的窗口；托盘菜单弹出时应用窗口会失去焦点，动作发给这个窗口
+    last_focused: Mutex<Option<String>>,
+    // 当前按着修饰键的窗口
+    modifiers_held: Mutex<HashSet<String>>,
+}
+
+impl FocusTracker {
+    // 窗口获得或失去焦点；失去焦点时收不到 keyup，修饰键状态一并清除
+    pub fn set_focused(&self, label: &str, focused: bool) {
+        if focused {
+            *self.last_focused.lock().unwrap() = Some(label.to_string());
+        } else {
+            self.modifiers_held.lock().unwrap().remove(label);
+        }
+    }
+
+    // 窗口被销毁后不再作为菜单动作的目标
+    pub fn forget(&self, label: &str) {
+        let mut last_focused = self.last_focused.lock().unwrap();
+        if last_focused.as_deref() == Some(label) {
+            *last_focused = None;
+        }
+        self.modifiers_held.lock().unwrap().remove(label);
+    }
+
+    pub fn last_focused(&self) -> Option<String> {
+        self.last_focused.lock().unwrap().clone()
+    }
+
+    pub fn set_modifiers(&self, label: &str, held: bool) {
+        let mut modifiers_held = self.modifiers_held.lock().unwrap();
+        if held {
+            modifiers_held.insert(label.to_string());
+        } else {
+            modifiers_held.remove(label);
+        }
+    }
+
+    pub fn modifiers_held(&self, label: &str) -> bool {
+        self.modifiers_held.lock().unwrap().contains(label)
+    }
+}
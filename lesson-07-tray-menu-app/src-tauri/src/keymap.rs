@@ -0,0 +1,263 @@
+// 快捷键映射：默认快捷键写在 menus/menu.json 中，用户可以在应用配置目录下的 keymap.json 中覆盖
+// keymap.json 的内容为 { "菜单项 ID": "快捷键" | null }，null 表示取消该菜单项的快捷键
+// 快捷键的写法与 tauri 菜单相同，例如 "CmdOrCtrl+Shift+Z"，用 muda（tauri 的菜单库）解析，
+// 所以 "CmdOrCtrl+N" 与 "Ctrl+N" 在 Windows / Linux 上被视为同一个快捷键
+use muda::accelerator::Accelerator;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::{CommandError, IoOperation, Resource};
+use crate::menu::MenuNode;
+
+// 某个菜单项的快捷键设置
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyBinding {
+    pub id: String,
+    // 菜单项在当前语言下的文字
+    pub label: String,
+    // 菜单配置中的默认快捷键
+    pub default: Option<String>,
+    // 实际生效的快捷键
+    pub accelerator: Option<String>,
+    // 是否被用户覆盖过
+    pub customized: bool,
+}
+
+// 解析快捷键，失败时给出 muda 的错误说明
+pub fn parse(accelerator: &str) -> Result<Accelerator, CommandError> {
+    accelerator
+        .parse()
+        .map_err(
+            |e: muda::accelerator::AcceleratorParseError| CommandError::InvalidShortcut {
+                accelerator: accelerator.to_string(),
+                reason: e.to_string(),
+            },
+        )
+}
+
+pub struct Keymap {
+    file: PathBuf,
+    // 应用菜单中的菜单项，按菜单中的顺序排列
+    items: Vec<MenuNode>,
+    // 用户的覆盖设置（菜单项 ID -> 快捷键）
+    overrides: Mutex<BTreeMap<String, Option<String>>>,
+}
+
+impl Keymap {
+    // 加载用户的覆盖设置；未知的菜单项、无法解析或互相冲突的快捷键会被忽略并打印日志
+    pub fn load(file: PathBuf, items: Vec<MenuNode>) -> Self {
+        let mut keymap = Keymap {
+            file,
+            items,
+            overrides: Mutex::new(BTreeMap::new()),
+        };
+        let overrides = fs::read_to_string(&keymap.file)
+            .ok()
+            .and_then(|text| {
+                match serde_json::from_str::<BTreeMap<String, Option<String>>>(&text) {
+                    Ok(overrides) => Some(overrides),
+                    Err(e) => {
+                        eprintln!("[RUST] 快捷键配置解析失败，使用默认快捷键: {:?}", e);
+                        None
+                    }
+                }
+            })
+            .unwrap_or_default();
+
+        let mut valid = BTreeMap::new();
+        for (id, accelerator) in overrides {
+            if keymap.item(&id).is_none() {
+                eprintln!("[RUST] 快捷键配置中的菜单项不存在，已忽略: {}", id);
+                continue;
+            }
+            if let Some(accelerator) = &accelerator {
+                if let Err(e) = parse(accelerator) {
+                    eprintln!("[RUST] 快捷键配置无效，已忽略: {}", e);
+                    continue;
+                }
+            }
+            valid.insert(id, accelerator);
+        }
+
+        // 互相冲突的覆盖项都退回默认值，直到没有冲突（默认快捷键之间的冲突无法自动解决）
+        loop {
+            let conflicting: Vec<String> = keymap
+                .conflicts(&valid)
+                .into_iter()
+                .flat_map(|(a, b)| [a, b])
+                .filter(|id| valid.contains_key(id))
+                .collect();
+            if conflicting.is_empty() {
+                break;
+            }
+            for id in conflicting {
+                if let Some(accelerator) = valid.remove(&id) {
+                    eprintln!(
+                        "[RUST] 快捷键 {:?} 与其他菜单项冲突，{} 恢复默认快捷键",
+                        accelerator, id
+                    );
+                }
+            }
+        }
+
+        *keymap.overrides.get_mut().unwrap() = valid;
+        keymap
+    }
+
+    fn item(&self, id: &str) -> Option<&MenuNode> {
+        self.items
+            .iter()
+            .find(|item| item.id.as_deref() == Some(id))
+    }
+
+    // 在给定的覆盖设置下，每个菜单项实际生效的快捷键
+    fn effective(
+        &self,
+        overrides: &BTreeMap<String, Option<String>>,
+    ) -> Vec<(String, Option<String>)> {
+        self.items
+            .iter()
+            .filter_map(|item| {
+                let id = item.id.clone()?;
+                let accelerator = match overrides.get(&id) {
+                    Some(accelerator) => accelerator.clone(),
+                    None => item.accelerator.clone(),
+                };
+                Some((id, accelerator))
+            })
+            .collect()
+    }
+
+    // 找出使用同一个快捷键的菜单项（两两列出）
+    fn conflicts(&self, overrides: &BTreeMap<String, Option<String>>) -> Vec<(String, String)> {
+        let mut owners: HashMap<Accelerator, String> = HashMap::new();
+        let mut conflicts = Vec::new();
+        for (id, accelerator) in self.effective(overrides) {
+            let Some(accelerator) = accelerator.and_then(|a| parse(&a).ok()) else {
+                continue;
+            };
+            match owners.get(&accelerator) {
+                Some(owner) => conflicts.push((owner.clone(), id)),
+                None => {
+                    owners.insert(accelerator, id);
+                }
+            }
+        }
+        conflicts
+    }
+
+    // 检查覆盖设置是否会造成冲突；给出 id 时只检查与该菜单项有关的冲突
+    fn check_conflicts(
+        &self,
+        overrides: &BTreeMap<String, Option<String>>,
+        id: Option<&str>,
+    ) -> Result<(), CommandError> {
+        let effective: HashMap<String, Option<String>> =
+            self.effective(overrides).into_iter().collect();
+        for (a, b) in self.conflicts(overrides) {
+            let (target, other) = match id {
+                Some(id) if a == id => (a, b),
+                Some(id) if b == id => (b, a),
+                Some(_) => continue,
+                None => (b, a),
+            };
+            return Err(CommandError::ShortcutConflict {
+                accelerator: effective[&target].clone().unwrap_or_default(),
+                id: other,
+            });
+        }
+        Ok(())
+    }
+
+    // 当前生效的快捷键（菜单项 ID -> 快捷键），构建菜单时使用
+    pub fn accelerators(&self) -> HashMap<String, Option<String>> {
+        let overrides = self.overrides.lock().unwrap();
+        self.effective(&overrides).into_iter().collect()
+    }
+
+    pub fn bindings(&self) -> Vec<KeyBinding> {
+        let overrides = self.overrides.lock().unwrap();
+        self.items
+            .iter()
+            .filter_map(|item| {
+                let id = item.id.clone()?;
+                let customized = overrides.get(&id);
+                Some(KeyBinding {
+                    label: item.text(),
+                    default: item.accelerator.clone(),
+                    accelerator: match customized {
+                        Some(accelerator) => accelerator.clone(),
+                        None => item.accelerator.clone(),
+                    },
+                    customized: customized.is_some(),
+                    id,
+                })
+            })
+            .collect()
+    }
+
+    // 修改某个菜单项的快捷键，None 表示取消快捷键
+    // 快捷键已被其他菜单项使用时返回 ShortcutConflict，需要先修改或取消那个菜单项的快捷键
+    pub fn set(&self, id: &str, accelerator: Option<String>) -> Result<(), CommandError> {
+        let item = self.item(id).ok_or_else(|| CommandError::NotFound {
+            resource: Resource::MenuItem,
+            id: id.to_string(),
+        })?;
+        let accelerator = accelerator
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty());
+        if let Some(accelerator) = &accelerator {
+            parse(accelerator)?;
+        }
+
+        let mut overrides = self.overrides.lock().unwrap();
+        let mut updated = overrides.clone();
+        if accelerator == item.accelerator {
+            updated.remove(id);
+        } else {
+            updated.insert(id.to_string(), accelerator.clone());
+        }
+        self.check_conflicts(&updated, Some(id))?;
+
+        self.persist(&updated)?;
+        *overrides = updated;
+        Ok(())
+    }
+
+    // 恢复某个菜单项（id 为 None 时为全部菜单项）的默认快捷键
+    pub fn reset(&self, id: Option<&str>) -> Result<(), CommandError> {
+        let mut overrides = self.overrides.lock().unwrap();
+        let mut updated = overrides.clone();
+        match id {
+            Some(id) => {
+                self.item(id).ok_or_else(|| CommandError::NotFound {
+                    resource: Resource::MenuItem,
+                    id: id.to_string(),
+                })?;
+                updated.remove(id);
+            }
+            None => updated.clear(),
+        }
+        // 恢复默认值后可能与其他菜单项的自定义快捷键冲突
+        self.check_conflicts(&updated, id)?;
+
+        self.persist(&updated)?;
+        *overrides = updated;
+        Ok(())
+    }
+
+    fn persist(&self, overrides: &BTreeMap<String, Option<String>>) -> Result<(), CommandError> {
+        let write_error = |e| CommandError::io(IoOperation::Write, &self.file, e);
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir).map_err(write_error)?;
+        }
+        let text = serde_json::to_string_pretty(overrides).map_err(|e| CommandError::Internal {
+            reason: e.to_string(),
+        })?;
+        fs::write(&self.file, text).map_err(write_error)
+    }
+}
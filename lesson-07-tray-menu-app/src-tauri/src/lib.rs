@@ -1,14 +1,42 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, State};
 
-// 统计信息结构体
-#[derive(Serialize, Deserialize)]
-struct Statistics {
-    sum: f64,
-    average: f64,
-    max: f64,
-    min: f64,
-}
+mod actions;
+mod autosave;
+mod clipboard;
+mod document;
+mod focus;
+mod keymap;
+mod menu;
+mod recent;
+mod text_encoding;
+mod zoom;
+
+// 与 my-first-tauri-app 共用的模块，代码在 app-core 中
+use app_core::{atomic_write, csv_import, decimal, error, expression, histogram, history, i18n, outliers, regression, statistics, stream_sessions, stream_stats};
+use app_core::current_timestamp;
+
+use actions::{ActionInfo, ActionMatch};
+use autosave::{Autosave, RecoveryInfo};
+use clipboard::{ClipboardContent, ClipboardEntry, ClipboardKind, ClipboardService};
+use csv_import::{CsvAnalysis, CsvImportOptions};
+use document::{DocumentChange, DocumentInfo, DocumentSettings, DocumentState, Documents, EditOperation, EncodingConversion, LineEnding};
+use error::{CommandError, IoOperation, Resource, ValueSource};
+use focus::FocusTracker;
+use histogram::{Histogram, HistogramOptions};
+use history::{HistoryEntry, HistoryStore, Operation};
+use i18n::{Locale, LocaleSettings};
+use keymap::{KeyBinding, Keymap};
+use menu::{ActionSource, MenuAction, MenuActionEvent, MenuCommand, MenuConfig, MenuItemState, MenuItemUpdate, MenuRegistry};
+use outliers::{OutlierOptions, OutlierReport};
+use recent::RecentFiles;
+use regression::{FittedModel, Prediction, RegressionOptions, RegressionResult};
+use statistics::{Statistics, StatisticsOptions};
+use stream_sessions::StreamSessions;
+use stream_stats::{StreamOptions, StreamStatistics};
+use zoom::{ZoomLevel, ZoomSettings, ZoomStep};
 
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -18,11 +46,7 @@ fn greet(name: &str) -> String {
 // 获取当前时间戳（Unix时间戳，秒）
 #[tauri::command]
 fn get_timestamp() -> u64 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
+    current_timestamp()
 }
 
 // 获取系统信息
@@ -31,54 +55,539 @@ fn get_system_info() -> String {
     format!("操作系统类型: {}", std::env::consts::OS)
 }
 
-// 安全除法：正确处理除零错误
+// 菜单内容变化后重建应用菜单和托盘菜单，失败只打印日志
+fn refresh_menu(app: &AppHandle) {
+    if let Err(e) = menu::refresh(app) {
+        eprintln!("[RUST] 重建菜单失败: {:?}", e);
+    }
+}
+
+// 切换界面语言：保存设置，重建应用菜单和托盘菜单，并向前端广播 "locale-changed" 事件
+// locale 为 null 时跟随系统语言；返回实际生效的语言
+fn change_locale(app: &AppHandle, locale: Option<Locale>) -> Result<Locale, CommandError> {
+    use tauri::Manager;
+
+    let effective = app.state::<LocaleSettings>().select(locale)?;
+    refresh_menu(app);
+    let _ = app.emit("locale-changed", effective);
+    eprintln!("[RUST] 界面语言已切换为: {}", effective.tag());
+    Ok(effective)
+}
+
+// 设置界面语言（"zh-CN" / "en-US" / null），菜单、托盘和命令错误提示都会随之切换
+// 错误码 code 与语言无关，前端应根据它而不是 message 做判断
+#[tauri::command]
+fn set_locale(app: AppHandle, locale: Option<Locale>) -> Result<Locale, CommandError> {
+    change_locale(&app, locale)
+}
+
+// 获取当前界面语言
+#[tauri::command]
+fn get_locale() -> Locale {
+    Locale::current()
+}
+
+// 记录刚打开或保存的文件，更新“最近打开”子菜单；返回最新的列表
+#[tauri::command]
+fn add_recent_file(app: AppHandle, path: PathBuf, recent: State<'_, RecentFiles>) -> Result<Vec<PathBuf>, CommandError> {
+    let paths = recent.add(path)?;
+    refresh_menu(&app);
+    Ok(paths)
+}
+
+// 获取最近打开的文件（最近的在前），已经不存在的文件会被移除
+#[tauri::command]
+fn list_recent_files(recent: State<'_, RecentFiles>) -> Vec<PathBuf> {
+    recent.paths()
+}
+
+#[tauri::command]
+fn clear_recent_files(app: AppHandle, recent: State<'_, RecentFiles>) {
+    recent.clear();
+    refresh_menu(&app);
+}
+
+// 设置最近打开列表保留的条数（1 ~ 50，默认 10）
+#[tauri::command]
+fn set_recent_files_limit(app: AppHandle, limit: usize, recent: State<'_, RecentFiles>) -> Result<Vec<PathBuf>, CommandError> {
+    let paths = recent.set_limit(limit)?;
+    refresh_menu(&app);
+    Ok(paths)
+}
+
+// 列出应用菜单中每个菜单项的快捷键（默认值和当前生效的值）
+#[tauri::command]
+fn list_key_bindings(keymap: State<'_, Keymap>) -> Vec<KeyBinding> {
+    keymap.bindings()
+}
+
+// 修改菜单项的快捷键，accelerator 为 null 表示取消快捷键；保存后立即重建菜单
+// 快捷键无法解析时返回 InvalidShortcut，已被其他菜单项使用时返回 ShortcutConflict
+#[tauri::command]
+fn set_key_binding(app: AppHandle, id: String, accelerator: Option<String>, keymap: State<'_, Keymap>) -> Result<Vec<KeyBinding>, CommandError> {
+    keymap.set(&id, accelerator)?;
+    refresh_menu(&app);
+    Ok(keymap.bindings())
+}
+
+// 恢复默认快捷键，id 为 null 时恢复全部菜单项
+#[tauri::command]
+fn reset_key_bindings(app: AppHandle, id: Option<String>, keymap: State<'_, Keymap>) -> Result<Vec<KeyBinding>, CommandError> {
+    keymap.reset(id.as_deref())?;
+    refresh_menu(&app);
+    Ok(keymap.bindings())
+}
+
+// 命令面板：列出所有动作（菜单项、托盘菜单项和命令），按模糊匹配的得分排序
+// query 为空时按菜单中的顺序返回全部动作
+#[tauri::command]
+fn search_actions(app: AppHandle, query: String, limit: Option<usize>) -> Vec<ActionMatch> {
+    actions::search(actions::list(&app), &query, limit)
+}
+
+// 列出所有动作，按菜单中的顺序排列
+#[tauri::command]
+fn list_actions(app: AppHandle) -> Vec<ActionInfo> {
+    actions::list(&app)
+}
+
+// 执行动作，与点击对应的菜单项效果相同；被禁用的菜单项返回 ActionDisabled
+#[tauri::command]
+fn execute_action(app: AppHandle, id: String) -> Result<(), CommandError> {
+    if let Ok(MenuItemState { enabled: Some(false), .. }) = menu::item_state(&app, &id) {
+        return Err(CommandError::ActionDisabled { id });
+    }
+    dispatch_action(&app, &id, ActionSource::Palette)
+}
+
+// 获取窗口的缩放比例（百分比），label 省略时为调用命令的窗口
+#[tauri::command]
+fn get_zoom(window: tauri::WebviewWindow, label: Option<String>, zoom: State<'_, ZoomSettings>) -> ZoomLevel {
+    zoom.level(label.as_deref().unwrap_or(window.label()))
+}
+
+// 设置窗口的缩放比例（百分比），超出允许范围时取最近的边界值；label 省略时为调用命令的窗口
+#[tauri::command]
+fn set_zoom(app: AppHandle, window: tauri::WebviewWindow, percent: u32, label: Option<String>, zoom: State<'_, ZoomSettings>) -> ZoomLevel {
+    let level = zoom.set(label.as_deref().unwrap_or(window.label()), percent);
+    apply_zoom(&app, &level);
+    level
+}
+
+// 设置允许的缩放范围（min 为 25 ~ 100，max 为 100 ~ 500），超出新范围的窗口会被调整
+#[tauri::command]
+fn set_zoom_range(app: AppHandle, min: u32, max: u32, zoom: State<'_, ZoomSettings>) -> Result<(), CommandError> {
+    for level in zoom.set_range(min, max)? {
+        apply_zoom(&app, &level);
+    }
+    Ok(())
+}
+
+// 新建文档（text 省略时为空文档），并设为调用窗口正在编辑的文档
+#[tauri::command]
+fn create_document(app: AppHandle, window: tauri::WebviewWindow, text: Option<String>, documents: State<'_, Documents>) -> DocumentState {
+    let state = documents.create(window.label(), text.unwrap_or_default());
+    refresh_edit_menu(&app);
+    state
+}
+
+#[tauri::command]
+fn get_document(id: u64, documents: State<'_, Documents>) -> Result<DocumentState, CommandError> {
+    documents.state(id)
+}
+
+// 打开文件，并设为调用窗口正在编辑的文档；文件已经打开时返回已有的文档
+// 编码按 BOM、UTF-8 校验和 chardetng 的猜测检测，返回的 encoding 为检测到的编码
+#[tauri::command]
+fn open_document(app: AppHandle, window: tauri::WebviewWindow, path: PathBuf) -> Result<DocumentState, CommandError> {
+    open_document_file(&app, window.label(), &path)
+}
+
+// 保存到文档对应的文件，按原来的编码和换行符风格写入；新建的文档需要使用 save_document_as
+// 有编码无法表示的字符时返回 Unrepresentable，不会有损地写入
+// 先写临时文件再原子替换，目标目录只读时返回 ReadOnlyDirectory
+#[tauri::command]
+fn save_document(app: AppHandle, id: u64) -> Result<DocumentInfo, CommandError> {
+    save_document_file(&app, id, None)
+}
+
+// 另存为：之后文档对应新的文件
+#[tauri::command]
+fn save_document_as(app: AppHandle, id: u64, path: PathBuf) -> Result<DocumentInfo, CommandError> {
+    save_document_file(&app, id, Some(path))
+}
+
+// 关闭文档；有未保存的修改时返回 UnsavedChanges，discard 为 true 时放弃修改
+#[tauri::command]
+fn close_document(app: AppHandle, id: u64, discard: Option<bool>, documents: State<'_, Documents>, autosave: State<'_, Autosave>) -> Result<(), CommandError> {
+    documents.close(id, discard.unwrap_or(false))?;
+    autosave.discard(id);
+    refresh_edit_menu(&app);
+    Ok(())
+}
+
+// 把文档转换为以 encoding 保存（例如 "UTF-8"、"GB18030"、"Shift_JIS"、"UTF-16LE"），下次保存时生效
+// bom 省略时只有 UTF-16 写入 BOM；返回值中列出新编码无法表示的字符，删除或替换后才能保存
+#[tauri::command]
+fn convert_document_encoding(id: u64, encoding: String, bom: Option<bool>, documents: State<'_, Documents>) -> Result<EncodingConversion, CommandError> {
+    documents.convert_encoding(id, text_encoding::lookup(&encoding)?, bom)
+}
+
+// 改为以 lineEnding（"lf" 或 "crlf"）保存，作为一步撤销；文本中的换行始终为 "\n"，所以 changes 为空
+#[tauri::command]
+fn convert_line_endings(app: AppHandle, id: u64, line_ending: LineEnding, documents: State<'_, Documents>) -> Result<DocumentChange, CommandError> {
+    let change = documents.convert_line_ending(id, line_ending)?;
+    refresh_edit_menu(&app);
+    Ok(change)
+}
+
+// 删除每行末尾的空白字符，作为一步撤销
+#[tauri::command]
+fn trim_trailing_whitespace(app: AppHandle, id: u64, documents: State<'_, Documents>) -> Result<DocumentChange, CommandError> {
+    let change = documents.trim_trailing_whitespace(id)?;
+    refresh_edit_menu(&app);
+    Ok(change)
+}
+
+// 文档不以换行结尾时在末尾补上换行，作为一步撤销
+#[tauri::command]
+fn ensure_final_newline(app: AppHandle, id: u64, documents: State<'_, Documents>) -> Result<DocumentChange, CommandError> {
+    let change = documents.ensure_final_newline(id)?;
+    refresh_edit_menu(&app);
+    Ok(change)
+}
+
+// 所有打开的文档（不含全文）
+#[tauri::command]
+fn list_documents(documents: State<'_, Documents>) -> Vec<DocumentInfo> {
+    documents.list()
+}
+
+// 编辑器的选区变化时报告选中的范围（UTF-16 位置），“剪切”“复制”菜单项据此由 Rust 读写剪贴板
+#[tauri::command]
+fn set_document_selection(window: tauri::WebviewWindow, id: u64, start: usize, end: usize, documents: State<'_, Documents>) -> Result<(), CommandError> {
+    documents.select(window.label(), id, start, end)
+}
+
+// 修改文档：edits 为 { kind: "insert" | "delete" | "replace", offset, length?, text? } 的列表，
+// 位置按 UTF-16 计算，每一项相对于前一项修改之后的文本；整组作为一步撤销
+// 返回修改后的文档和实际生效的修改，调用窗口随之切换到这个文档
+#[tauri::command]
+fn edit_document(app: AppHandle, window: tauri::WebviewWindow, id: u64, edits: Vec<EditOperation>, documents: State<'_, Documents>) -> Result<DocumentChange, CommandError> {
+    documents.activate(window.label(), id)?;
+    let change = documents.edit(id, &edits)?;
+    refresh_edit_menu(&app);
+    Ok(change)
+}
+
+// 撤销一步，返回撤销后的文档和为此做的修改；没有可撤销的步骤时 changes 为空
+#[tauri::command]
+fn undo_document(app: AppHandle, id: u64, documents: State<'_, Documents>) -> Result<DocumentChange, CommandError> {
+    let change = documents.undo(id)?;
+    refresh_edit_menu(&app);
+    Ok(change)
+}
+
+#[tauri::command]
+fn redo_document(app: AppHandle, id: u64, documents: State<'_, Documents>) -> Result<DocumentChange, CommandError> {
+    let change = documents.redo(id)?;
+    refresh_edit_menu(&app);
+    Ok(change)
+}
+
+// 记录保存点：撤销回这个状态时文档重新变为未修改
+#[tauri::command]
+fn mark_document_saved(id: u64, documents: State<'_, Documents>) -> Result<DocumentState, CommandError> {
+    documents.with(id, |document| {
+        document.mark_saved();
+        Ok(())
+    })?;
+    documents.state(id)
+}
+
+// 文档设置：撤销步数、保存时保留的备份个数和自动保存的间隔
 #[tauri::command]
-fn safe_divide(a: f64, b: f64) -> Result<f64, String> {
+fn get_document_settings(documents: State<'_, Documents>) -> DocumentSettings {
+    documents.settings()
+}
+
+// 设置保存时保留的备份个数（0 ~ 20，默认 0 即不备份）
+#[tauri::command]
+fn set_backup_count(count: usize, documents: State<'_, Documents>) -> Result<(), CommandError> {
+    documents.set_backups(count)
+}
+
+// 设置自动保存的间隔（5 ~ 3600 秒，默认 30），0 表示只在关闭窗口和退出时写入恢复快照
+#[tauri::command]
+fn set_autosave_interval(seconds: u64, documents: State<'_, Documents>) -> Result<(), CommandError> {
+    documents.set_autosave_interval(seconds)
+}
+
+// 以前运行时（崩溃或被强制结束）留下的恢复快照，最新的在前
+#[tauri::command]
+fn list_recovery_snapshots(autosave: State<'_, Autosave>) -> Vec<RecoveryInfo> {
+    autosave.list()
+}
+
+// 从恢复快照还原文档，并设为调用窗口正在编辑的文档；还原的内容作为一步可以撤销的修改，
+// 撤销即回到磁盘上的版本。还原后删除快照
+#[tauri::command]
+fn recover_snapshot(app: AppHandle, window: tauri::WebviewWindow, id: String, autosave: State<'_, Autosave>, documents: State<'_, Documents>) -> Result<DocumentState, CommandError> {
+    let snapshot = autosave.read(&id)?;
+    let format = snapshot.encoding.as_deref().and_then(|label| text_encoding::lookup(label).ok()).map(|encoding| (encoding, snapshot.bom));
+    let state = documents.recover(window.label(), snapshot.path.as_deref(), format, snapshot.line_ending, snapshot.text)?;
+    if let Err(e) = autosave.remove(&id) {
+        eprintln!("[RUST] 删除恢复快照 {} 失败: {}", id, e);
+    }
+    refresh_edit_menu(&app);
+    Ok(state)
+}
+
+// 丢弃一个恢复快照
+#[tauri::command]
+fn discard_recovery_snapshot(id: String, autosave: State<'_, Autosave>) -> Result<(), CommandError> {
+    autosave.remove(&id)
+}
+
+// 丢弃所有恢复快照，返回丢弃的个数
+#[tauri::command]
+fn clear_recovery_snapshots(autosave: State<'_, Autosave>) -> usize {
+    autosave.clear()
+}
+
+// 设置撤销步数（1 ~ 10000，默认 100），超出的旧步骤立即丢弃
+#[tauri::command]
+fn set_undo_depth(app: AppHandle, depth: usize, documents: State<'_, Documents>) -> Result<(), CommandError> {
+    documents.set_depth(depth)?;
+    refresh_edit_menu(&app);
+    Ok(())
+}
+
+// 读取剪贴板中的文本，没有文本时返回 null
+#[tauri::command]
+fn read_clipboard_text(clipboard: State<'_, ClipboardService>) -> Result<Option<String>, CommandError> {
+    Ok(clipboard.read(ClipboardKind::Text)?.and_then(|content| content.plain_text()))
+}
+
+#[tauri::command]
+fn write_clipboard_text(text: String, clipboard: State<'_, ClipboardService>) -> Result<(), CommandError> {
+    clipboard.write(ClipboardContent::Text { text })
+}
+
+// 读取剪贴板中的 HTML（{ kind: "html", html, text }），没有 HTML 时返回 null
+#[tauri::command]
+fn read_clipboard_html(clipboard: State<'_, ClipboardService>) -> Result<Option<ClipboardContent>, CommandError> {
+    clipboard.read(ClipboardKind::Html)
+}
+
+// 写入 HTML，text 为不支持 HTML 的应用粘贴时使用的纯文本
+#[tauri::command]
+fn write_clipboard_html(html: String, text: Option<String>, clipboard: State<'_, ClipboardService>) -> Result<(), CommandError> {
+    clipboard.write(ClipboardContent::Html { html, text })
+}
+
+// 读取剪贴板中的图片（{ kind: "image", width, height, rgba }），没有图片时返回 null
+#[tauri::command]
+fn read_clipboard_image(clipboard: State<'_, ClipboardService>) -> Result<Option<ClipboardContent>, CommandError> {
+    clipboard.read(ClipboardKind::Image)
+}
+
+// 写入图片，rgba 为逐行排列的 RGBA 像素，长度必须为 width * height * 4
+#[tauri::command]
+fn write_clipboard_image(width: usize, height: usize, rgba: Vec<u8>, clipboard: State<'_, ClipboardService>) -> Result<(), CommandError> {
+    clipboard.write(ClipboardContent::Image { width, height, rgba })
+}
+
+// 剪贴板历史（最近的在前），query 按文字内容筛选
+#[tauri::command]
+fn list_clipboard_history(query: Option<String>, limit: Option<usize>, clipboard: State<'_, ClipboardService>) -> Vec<ClipboardEntry> {
+    clipboard.history(query.as_deref().unwrap_or_default(), limit)
+}
+
+// 把历史中的一条放回剪贴板，并像“粘贴”菜单项一样发给当前窗口
+#[tauri::command]
+fn paste_clipboard_entry(app: AppHandle, id: u64, clipboard: State<'_, ClipboardService>) -> Result<ClipboardContent, CommandError> {
+    let content = clipboard.restore(id)?;
+    emit_menu_action(&app, MenuActionEvent {
+        clipboard: Some(content.clone()),
+        ..MenuActionEvent::new(MenuAction::Paste, ActionSource::Palette)
+    });
+    Ok(content)
+}
+
+#[tauri::command]
+fn clear_clipboard_history(clipboard: State<'_, ClipboardService>) {
+    clipboard.clear_history();
+}
+
+// 设置剪贴板历史保留的条数（1 ~ 500，默认 50）
+#[tauri::command]
+fn set_clipboard_history_limit(limit: usize, clipboard: State<'_, ClipboardService>) -> Result<(), CommandError> {
+    clipboard.set_limit(limit)
+}
+
+// 读取菜单项（包括托盘菜单项）的当前状态：是否启用、文字、勾选状态
+#[tauri::command]
+fn get_menu_item(app: AppHandle, id: String) -> Result<MenuItemState, CommandError> {
+    menu::item_state(&app, &id)
+}
+
+// 批量修改菜单项的状态，前端据此让菜单与编辑器保持一致，
+// 例如没有打开文档时禁用“保存”、没有可撤销的操作时禁用“撤销”
+// 按顺序逐项修改，遇到错误时停止（之前的修改已生效）；返回修改后的状态
+#[tauri::command]
+fn update_menu_items(app: AppHandle, updates: Vec<MenuItemUpdate>) -> Result<Vec<MenuItemState>, CommandError> {
+    updates
+        .iter()
+        .map(|update| menu::update_item(&app, &update.id, &update.state))
+        .collect()
+}
+
+// 前端上报当前窗口中是否按着修饰键，用于判断菜单动作是点击还是快捷键触发的
+#[tauri::command]
+fn report_modifiers(window: tauri::WebviewWindow, held: bool, focus: State<'_, FocusTracker>) {
+    focus.set_modifiers(window.label(), held);
+}
+
+// 安全除法的计算逻辑：正确处理除零错误
+fn divide_values(a: f64, b: f64) -> Result<f64, CommandError> {
     if b == 0.0 {
-        Err("除数不能为零！".to_string())
+        Err(CommandError::DivisionByZero)
     } else {
         Ok(a / b)
     }
 }
 
-// 处理数字数组，返回统计信息
+// 安全除法：结果会记录到计算历史
 #[tauri::command]
-fn process_numbers(numbers: Vec<f64>) -> Statistics {
-    if numbers.is_empty() {
-        return Statistics {
-            sum: 0.0,
-            average: 0.0,
-            max: 0.0,
-            min: 0.0,
-        };
-    }
-    
-    let sum: f64 = numbers.iter().sum();
-    let average = sum / numbers.len() as f64;
-    let max = numbers.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    let min = numbers.iter().cloned().fold(f64::INFINITY, f64::min);
-    
-    Statistics {
-        sum,
-        average,
-        max,
-        min,
-    }
+fn safe_divide(a: f64, b: f64, history: State<'_, HistoryStore>) -> Result<f64, CommandError> {
+    let result = divide_values(a, b);
+    history.record(Operation::SafeDivide { a, b }, &result);
+    result
 }
 
-// 计算器命令：接收运算类型和两个数字，返回计算结果
-// 使用Result类型来处理错误（特别是除零错误）
+// 处理数字数组，返回描述性统计信息
+// options 可指定额外的百分位数，以及 NaN / 无穷大的处理策略（reject / skip / propagate）
+// 空数组会返回错误，而不是一组全零的统计值
+#[tauri::command]
+fn process_numbers(
+    numbers: Vec<f64>,
+    options: Option<StatisticsOptions>,
+    history: State<'_, HistoryStore>,
+) -> Result<Statistics, CommandError> {
+    let options = options.unwrap_or_default();
+    let result = statistics::compute(&numbers, &options);
+    history.record(Operation::process_numbers(numbers, options), &result);
+    result
+}
+
+// 离群值检测命令：支持 Z 分数、修正 Z 分数（MAD）和 IQR 栅栏三种方法，
+// 返回被标记的下标和数值，以及剔除离群值后重新计算的统计信息
+#[tauri::command]
+fn detect_outliers(
+    numbers: Vec<f64>,
+    options: Option<OutlierOptions>,
+) -> Result<OutlierReport, CommandError> {
+    outliers::detect(&numbers, &options.unwrap_or_default())
+}
+
+// 直方图命令：返回箱边界和计数，前端直接用来画图
+// options.bins 支持固定箱数、固定箱宽以及 Sturges / Scott / Freedman–Diaconis 自动规则，
+// cumulative 和 density 控制累积与密度归一化
+#[tauri::command]
+fn compute_histogram(
+    numbers: Vec<f64>,
+    options: Option<HistogramOptions>,
+) -> Result<Histogram, CommandError> {
+    histogram::compute(&numbers, &options.unwrap_or_default())
+}
+
+// 回归分析命令：对成对的 x / y 数据拟合线性、多项式、指数或对数模型，
+// 返回系数、R²、残差，以及 options.predict 中各 x 处的预测值
+#[tauri::command]
+fn fit_regression(
+    x: Vec<f64>,
+    y: Vec<f64>,
+    options: Option<RegressionOptions>,
+) -> Result<RegressionResult, CommandError> {
+    regression::fit(&x, &y, &options.unwrap_or_default())
+}
+
+// 用 fit_regression 返回的模型在新的 x 处做预测
+#[tauri::command]
+fn predict_regression(model: FittedModel, x: Vec<f64>) -> Result<Vec<Prediction>, CommandError> {
+    regression::predict(&model, &x)
+}
+
+// 导入 CSV / TSV 文件并按列统计：自动识别分隔符和表头，
+// options.columns 可按列名或列序号选择要统计的列，无法解析的单元格会带行号返回
+#[tauri::command]
+fn analyze_csv(path: &str, options: Option<CsvImportOptions>) -> Result<CsvAnalysis, CommandError> {
+    csv_import::analyze(std::path::Path::new(path), &options.unwrap_or_default())
+}
+
+// ========== 流式统计 ==========
+// 适合传感器等海量数据：分多次推送，随时查询当前统计，内存占用不随样本数增长
+
+// 打开一个流式统计会话，返回会话 ID
+#[tauri::command]
+fn open_stats_session(
+    options: Option<StreamOptions>,
+    sessions: State<'_, StreamSessions>,
+) -> Result<u64, CommandError> {
+    sessions.open(&options.unwrap_or_default())
+}
+
+// 向会话推送一批数字，返回推送后的统计结果
+#[tauri::command]
+fn push_stats_batch(
+    session_id: u64,
+    numbers: Vec<f64>,
+    sessions: State<'_, StreamSessions>,
+) -> Result<StreamStatistics, CommandError> {
+    sessions.push(session_id, &numbers)
+}
+
+// 查询会话当前的统计结果
 #[tauri::command]
-fn calculate(operation: &str, a: f64, b: f64) -> Result<f64, String> {
+fn get_session_statistics(
+    session_id: u64,
+    sessions: State<'_, StreamSessions>,
+) -> Result<StreamStatistics, CommandError> {
+    sessions.snapshot(session_id)
+}
+
+// 订阅会话：之后每推送一批数据，都会通过 Channel 把最新统计发给前端
+#[tauri::command]
+fn subscribe_stats_session(
+    session_id: u64,
+    on_update: Channel<StreamStatistics>,
+    sessions: State<'_, StreamSessions>,
+) -> Result<(), CommandError> {
+    sessions.subscribe(session_id, on_update)
+}
+
+// 关闭会话，返回最终统计结果（会话中没有数据时为 null）
+#[tauri::command]
+fn close_stats_session(
+    session_id: u64,
+    sessions: State<'_, StreamSessions>,
+) -> Result<Option<StreamStatistics>, CommandError> {
+    sessions.close(session_id)
+}
+
+// 计算器的计算逻辑：接收运算类型和两个数字，返回计算结果
+// 使用Result类型来处理错误（特别是除零错误）
+fn calculate_values(operation: &str, a: f64, b: f64) -> Result<f64, CommandError> {
     // 验证输入是否为有效数字
     if a.is_nan() || b.is_nan() {
-        return Err("输入的数字无效".to_string());
+        return Err(CommandError::InvalidNumber { index: None, value: None });
     }
-    
+
     if a.is_infinite() || b.is_infinite() {
-        return Err("输入的数字超出范围".to_string());
+        return Err(CommandError::OutOfRange { source: ValueSource::Input });
     }
-    
+
     // 根据运算类型执行相应的计算
     match operation {
         "add" => Ok(a + b),
@@ -87,331 +596,698 @@ fn calculate(operation: &str, a: f64, b: f64) -> Result<f64, String> {
         "divide" => {
             // 处理除零错误
             if b == 0.0 {
-                Err("除数不能为零！".to_string())
+                Err(CommandError::DivisionByZero)
             } else {
                 Ok(a / b)
             }
         },
-        _ => Err(format!("不支持的运算类型: {}", operation))
+        _ => Err(CommandError::UnsupportedOperation { operation: operation.to_string() })
+    }
+}
+
+// 计算器命令：结果会记录到计算历史
+#[tauri::command]
+fn calculate(
+    operation: &str,
+    a: f64,
+    b: f64,
+    history: State<'_, HistoryStore>,
+) -> Result<f64, CommandError> {
+    let result = calculate_values(operation, a, b);
+    history.record(
+        Operation::Calculate {
+            operation: operation.to_string(),
+            a,
+            b,
+        },
+        &result,
+    );
+    result
+}
+
+// 表达式求值命令：支持运算优先级、括号、一元负号、乘方(^)、取模(%)
+// 以及 sin、cos、ln、sqrt、abs 等常用函数
+// 出错时 details 中带有 column / length，前端可以据此标出出错的位置
+#[tauri::command]
+fn evaluate_expression(
+    expression: &str,
+    history: State<'_, HistoryStore>,
+) -> Result<f64, CommandError> {
+    let result = expression::evaluate(expression);
+    history.record(
+        Operation::EvaluateExpression {
+            expression: expression.to_string(),
+        },
+        &result,
+    );
+    result
+}
+
+// 十进制高精度计算命令：a、b 以字符串传入，结果以精确的十进制字符串返回
+// calculate 仍然保持 f64 的行为，需要精确结果（如金额）时按次改用此命令
+// options 可指定保留的小数位数（precision）和舍入模式（halfEven / halfUp / truncate）
+#[tauri::command]
+fn calculate_decimal(
+    operation: &str,
+    a: &str,
+    b: &str,
+    options: Option<decimal::DecimalOptions>,
+    history: State<'_, HistoryStore>,
+) -> Result<String, CommandError> {
+    let options = options.unwrap_or_default();
+    let result = decimal::calculate(operation, a, b, options);
+    history.record(
+        Operation::CalculateDecimal {
+            operation: operation.to_string(),
+            a: a.to_string(),
+            b: b.to_string(),
+            options,
+        },
+        &result,
+    );
+    result
+}
+
+// ========== 计算历史 ==========
+
+// 按时间倒序列出历史记录
+#[tauri::command]
+fn list_history(limit: Option<usize>, history: State<'_, HistoryStore>) -> Vec<HistoryEntry> {
+    history.list(limit)
+}
+
+// 按关键字搜索历史记录（匹配运算类型、输入、结果和错误信息）
+#[tauri::command]
+fn search_history(
+    query: &str,
+    limit: Option<usize>,
+    history: State<'_, HistoryStore>,
+) -> Vec<HistoryEntry> {
+    history.search(query, limit)
+}
+
+// 删除一条历史记录，返回是否删除成功
+#[tauri::command]
+fn delete_history_entry(id: u64, history: State<'_, HistoryStore>) -> bool {
+    history.delete(id)
+}
+
+// 清空全部历史记录
+#[tauri::command]
+fn clear_history(history: State<'_, HistoryStore>) {
+    history.clear();
+}
+
+// 重放一条历史记录：用原来的输入重新计算一次，并作为新的历史条目返回
+#[tauri::command]
+fn replay_history_entry(id: u64, history: State<'_, HistoryStore>) -> Result<HistoryEntry, CommandError> {
+    let entry = history
+        .get(id)
+        .ok_or_else(|| CommandError::NotFound {
+            resource: error::Resource::HistoryEntry,
+            id: id.to_string(),
+        })?;
+
+    // 输入过多、只保存了一部分的记录无法按原样重放
+    if !entry.operation.is_complete() {
+        return Err(CommandError::invalid_argument("id", id, "completeHistoryEntry"));
+    }
+
+    let operation = entry.operation.clone();
+    let replayed = match &entry.operation {
+        Operation::Calculate { operation: op, a, b } => {
+            history.record(operation, &calculate_values(op, *a, *b))
+        }
+        Operation::SafeDivide { a, b } => history.record(operation, &divide_values(*a, *b)),
+        Operation::ProcessNumbers { numbers, options, .. } => {
+            history.record(operation, &statistics::compute(numbers, options))
+        }
+        Operation::EvaluateExpression { expression } => {
+            history.record(operation, &expression::evaluate(expression))
+        }
+        Operation::CalculateDecimal {
+            operation: op,
+            a,
+            b,
+            options,
+        } => history.record(operation, &decimal::calculate(op, a, b, *options)),
+    };
+    Ok(replayed)
+}
+
+// 导出历史记录到文件，format 支持 "json"（默认）和 "csv"，返回导出的条数
+#[tauri::command]
+fn export_history(
+    path: &str,
+    format: Option<String>,
+    history: State<'_, HistoryStore>,
+) -> Result<usize, CommandError> {
+    let format = format.unwrap_or_else(|| "json".to_string());
+    history.export(std::path::Path::new(path), &format.to_lowercase())
+}
+
+// 显示主窗口：托盘菜单的“显示窗口”使用，尽量保证窗口真正显示出来并获得焦点
+fn show_main_window(app: &AppHandle) {
+    use tauri::Manager;
+
+    eprintln!("[RUST] 显示窗口菜单项被点击");
+    // 显示主窗口 - 尝试多种方法获取窗口
+    let window = app.get_webview_window("main")
+        .or_else(|| {
+            eprintln!("[RUST] get_webview_window 失败，尝试 webview_windows");
+            let all_windows = app.webview_windows();
+            let window_labels: Vec<String> = all_windows.keys().cloned().collect();
+            eprintln!("[RUST] 所有可用窗口标签: {:?}", window_labels);
+            all_windows.get("main").cloned()
+        });
+    
+    if let Some(window) = window {
+        eprintln!("[RUST] ✅ 成功获取主窗口");
+        
+        // 检查窗口当前状态
+        if let Ok(is_visible) = window.is_visible() {
+            eprintln!("[RUST] 窗口当前可见状态: {}", is_visible);
+        }
+        
+        // 检查窗口是否最小化
+        if let Ok(is_minimized) = window.is_minimized() {
+            eprintln!("[RUST] 窗口是否最小化: {}", is_minimized);
+            if is_minimized {
+                // 先取消最小化
+                match window.unminimize() {
+                    Ok(_) => eprintln!("[RUST] 取消最小化成功"),
+                    Err(e) => eprintln!("[RUST] 取消最小化失败: {:?}", e),
+                }
+            }
+        }
+        
+        // 显示窗口 - 使用异步方式，确保窗口真正显示
+        match window.show() {
+            Ok(_) => {
+                eprintln!("[RUST] 窗口显示命令执行成功");
+                
+                // 等待一小段时间确保窗口显示
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                
+                // 再次检查窗口状态
+                if let Ok(is_visible) = window.is_visible() {
+                    eprintln!("[RUST] 显示后窗口可见状态: {}", is_visible);
+                    
+                    // 如果窗口仍然不可见，尝试其他方法
+                    if !is_visible {
+                        eprintln!("[RUST] ⚠️ 窗口显示后仍然不可见，尝试强制显示");
+                        
+                        // 尝试将窗口居中（确保窗口在可见区域）
+                        if let Err(e) = window.center() {
+                            eprintln!("[RUST] 窗口居中失败: {:?}", e);
+                        } else {
+                            eprintln!("[RUST] 窗口已居中");
+                        }
+                        
+                        // 再次尝试显示
+                        if let Err(e) = window.show() {
+                            eprintln!("[RUST] 再次显示窗口失败: {:?}", e);
+                        } else {
+                            eprintln!("[RUST] 再次显示窗口成功");
+                            std::thread::sleep(std::time::Duration::from_millis(100));
+                        }
+                    }
+                }
+                
+                // 设置焦点
+                match window.set_focus() {
+                    Ok(_) => eprintln!("[RUST] 窗口焦点设置成功"),
+                    Err(e) => eprintln!("[RUST] 窗口焦点设置失败: {:?}", e),
+                }
+                
+                // 最终检查窗口状态
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                if let Ok(is_visible) = window.is_visible() {
+                    eprintln!("[RUST] 窗口最终可见状态: {}", is_visible);
+                }
+            }
+            Err(e) => eprintln!("[RUST] ❌ 窗口显示失败: {:?}", e),
+        }
+    } else {
+        eprintln!("[RUST] ❌ 无法获取主窗口（标签：main）");
+        // 尝试列出所有窗口
+        let all_windows = app.webview_windows();
+        let window_labels: Vec<String> = all_windows.keys().cloned().collect();
+        eprintln!("[RUST] 所有可用窗口标签: {:?}", window_labels);
+        
+        // 如果主窗口不存在，尝试重新创建（作为最后手段）
+        eprintln!("[RUST] ⚠️ 主窗口不存在，可能需要重新创建");
+    }
+}
+
+// 隐藏主窗口
+fn hide_main_window(app: &AppHandle) {
+    use tauri::Manager;
+
+    eprintln!("[RUST] 隐藏窗口菜单项被点击");
+    // 隐藏主窗口
+    if let Some(window) = app.get_webview_window("main") {
+        match window.hide() {
+            Ok(_) => eprintln!("[RUST] 窗口隐藏成功"),
+            Err(e) => eprintln!("[RUST] 窗口隐藏失败: {:?}", e),
+        }
+    } else {
+        eprintln!("[RUST] ❌ 无法获取主窗口（标签：main）");
+    }
+}
+
+// 让窗口的缩放比例生效，并向该窗口发送 "zoom-changed" 事件
+fn apply_zoom(app: &AppHandle, level: &ZoomLevel) {
+    use tauri::Manager;
+
+    let Some(window) = app.get_webview_window(&level.window) else {
+        return;
+    };
+    if let Err(e) = window.set_zoom(level.scale()) {
+        eprintln!("[RUST] 设置窗口 {} 的缩放失败: {:?}", level.window, e);
+    }
+    let _ = app.emit_to(level.window.as_str(), "zoom-changed", level);
+}
+
+// 当前拥有焦点的窗口
+fn focused_window(app: &AppHandle) -> Option<String> {
+    use tauri::Manager;
+
+    app.webview_windows()
+        .into_iter()
+        .find(|(_, window)| window.is_focused().unwrap_or(false))
+        .map(|(label, _)| label)
+}
+
+// 菜单动作的目标窗口：拥有焦点的窗口；没有窗口拥有焦点时（例如从托盘触发）
+// 为最近一次获得焦点的窗口，都没有时为主窗口
+fn target_window(app: &AppHandle) -> String {
+    use tauri::Manager;
+
+    focused_window(app)
+        .or_else(|| app.state::<FocusTracker>().last_focused())
+        .unwrap_or_else(|| "main".to_string())
+}
+
+// 根据目标窗口正在编辑的文档设置撤销 / 重做菜单项的启用状态
+// 文档服务在菜单创建之后才注册，在此之前（例如启动时的焦点事件）什么也不做
+fn refresh_edit_menu(app: &AppHandle) {
+    use tauri::Manager;
+
+    let Some(documents) = app.try_state::<Documents>() else {
+        return;
+    };
+    let (can_undo, can_redo) = documents.undo_state(&target_window(app));
+    for (id, enabled) in [("undo", can_undo), ("redo", can_redo)] {
+        let update = MenuItemState {
+            enabled: Some(enabled),
+            ..Default::default()
+        };
+        if let Err(e) = menu::update_item(app, id, &update) {
+            eprintln!("[RUST] 更新菜单项 {} 失败: {:?}", id, e);
+        }
+    }
+}
+
+// 打开文件并设为窗口正在编辑的文档，同时记入“最近打开”
+fn open_document_file(app: &AppHandle, window: &str, path: &std::path::Path) -> Result<DocumentState, CommandError> {
+    use tauri::Manager;
+
+    let state = app.state::<Documents>().open(window, path)?;
+    if let Err(e) = app.state::<RecentFiles>().add(path.to_path_buf()) {
+        eprintln!("[RUST] 记录最近文件失败: {}", e);
+    }
+    refresh_menu(app);
+    refresh_edit_menu(app);
+    Ok(state)
+}
+
+// 保存文档（path 为 None 时保存到原来的文件），同时记入“最近打开”，并删除它的恢复快照
+fn save_document_file(app: &AppHandle, id: u64, path: Option<PathBuf>) -> Result<DocumentInfo, CommandError> {
+    use tauri::Manager;
+
+    let info = app.state::<Documents>().save(id, path)?;
+    app.state::<Autosave>().discard(id);
+    if let Some(path) = &info.path {
+        if let Err(e) = app.state::<RecentFiles>().add(path.clone()) {
+            eprintln!("[RUST] 记录最近文件失败: {}", e);
+        }
+        refresh_menu(app);
+    }
+    Ok(info)
+}
+
+// 立即为有未保存修改的文档写入恢复快照（关闭窗口和退出前调用）
+fn autosave_now(app: &AppHandle) {
+    use tauri::Manager;
+
+    if let (Some(autosave), Some(documents)) = (app.try_state::<Autosave>(), app.try_state::<Documents>()) {
+        autosave.snapshot(&documents);
+    }
+}
+
+// 按设置的间隔定时写入恢复快照，在后台线程中运行；间隔为 0 时只在关闭窗口和退出时写入
+fn run_autosave(app: AppHandle) {
+    use std::time::{Duration, Instant};
+    use tauri::Manager;
+
+    let mut last = Instant::now();
+    loop {
+        std::thread::sleep(Duration::from_secs(1));
+        let seconds = app.state::<Documents>().settings().autosave_seconds;
+        if seconds == 0 || last.elapsed() < Duration::from_secs(seconds) {
+            continue;
+        }
+        last = Instant::now();
+        app.state::<Autosave>().snapshot(&app.state::<Documents>());
+    }
+}
+
+// 文件菜单使用的打开 / 保存对话框
+fn document_dialog(app: &AppHandle) -> tauri_plugin_dialog::FileDialogBuilder<tauri::Wry> {
+    use tauri_plugin_dialog::DialogExt;
+
+    app.dialog()
+        .file()
+        .add_filter(i18n::t("dialog.textFiles"), &["txt", "md"])
+        .add_filter(i18n::t("dialog.allFiles"), &["*"])
+}
+
+// 菜单触发的文档操作没有调用方可以接收结果：成功时向窗口发送 event，失败时发送 "document-error"
+fn emit_document_result<T: serde::Serialize + Clone>(app: &AppHandle, window: &str, event: &str, result: Result<T, CommandError>) {
+    let sent = match result {
+        Ok(payload) => app.emit_to(window, event, payload),
+        Err(e) => {
+            eprintln!("[RUST] 文档操作失败: {}", e);
+            app.emit_to(window, "document-error", e)
+        }
+    };
+    if let Err(e) = sent {
+        eprintln!("[RUST] 发送文档事件到窗口 {} 失败: {:?}", window, e);
+    }
+}
+
+// 对目标窗口正在编辑的文档执行 f（撤销、重做、整理换行符等），结果以 "document-changed" 事件发给该窗口
+fn change_active_document(app: &AppHandle, f: impl FnOnce(&Documents, u64) -> Result<DocumentChange, CommandError>) -> Result<(), CommandError> {
+    use tauri::Manager;
+
+    let target = target_window(app);
+    let documents = app.state::<Documents>();
+    let Some(id) = documents.active(&target) else {
+        eprintln!("[RUST] 窗口 {} 没有正在编辑的文档", target);
+        return Ok(());
+    };
+    let change = f(&documents, id)?;
+    refresh_edit_menu(app);
+    let _ = app.emit_to(target.as_str(), "document-changed", &change);
+    Ok(())
+}
+
+// 剪切 / 复制目标窗口文档中选中的文字；文档中没有选中文字时交给前端处理
+fn copy_selection(app: &AppHandle, cut: bool, source: ActionSource) -> Result<(), CommandError> {
+    use tauri::Manager;
+
+    let target = target_window(app);
+    let documents = app.state::<Documents>();
+    let selected = match documents.active(&target) {
+        Some(id) => documents.selected_text(id)?.map(|text| (id, text)),
+        None => None,
+    };
+    let Some((id, text)) = selected else {
+        let action = if cut { MenuAction::Cut } else { MenuAction::Copy };
+        emit_menu_action(app, MenuActionEvent::new(action, source));
+        return Ok(());
+    };
+    // 先写入剪贴板，写入失败时不删除文字
+    app.state::<ClipboardService>().write(ClipboardContent::Text { text })?;
+    if cut {
+        let change = documents.delete_selection(id)?;
+        refresh_edit_menu(app);
+        let _ = app.emit_to(target.as_str(), "document-changed", &change);
+    }
+    Ok(())
+}
+
+// 把菜单动作发送给目标窗口
+fn emit_menu_action(app: &AppHandle, mut event: MenuActionEvent) {
+    event.window = focused_window(app);
+    let target = target_window(app);
+
+    if let Err(e) = app.emit_to(target.as_str(), "menu-action", &event) {
+        eprintln!("[RUST] 发送菜单事件到窗口 {} 失败: {:?}", target, e);
+    }
+}
+
+// 执行动作：应用菜单、托盘菜单和命令面板（execute_action）共用同一张分发表（见 menus/menu.json）
+fn dispatch_action(app: &AppHandle, id: &str, source: ActionSource) -> Result<(), CommandError> {
+    use tauri::Manager;
+
+    let command = app.state::<MenuRegistry>().command(id).ok_or_else(|| CommandError::NotFound {
+        resource: Resource::Action,
+        id: id.to_string(),
+    })?;
+    match command {
+        MenuCommand::Emit => {
+            eprintln!("[RUST] 菜单：{}", id);
+            emit_menu_action(app, MenuActionEvent::new(MenuAction::from_id(id), source));
+        }
+        MenuCommand::OpenRecent(path) => {
+            // 菜单构建之后文件可能已被删除，此时重建菜单把它移除
+            if !path.is_file() {
+                eprintln!("[RUST] 最近文件已不存在: {}", path.display());
+                refresh_menu(app);
+                return Err(CommandError::io(IoOperation::Read, &path, std::io::ErrorKind::NotFound.into()));
+            }
+            eprintln!("[RUST] 菜单：打开最近文件 {}", path.display());
+            let target = target_window(app);
+            let result = open_document_file(app, &target, &path);
+            emit_document_result(app, &target, "document-opened", result);
+        }
+        MenuCommand::NewDocument => {
+            let target = target_window(app);
+            let state = app.state::<Documents>().create(&target, String::new());
+            refresh_edit_menu(app);
+            let _ = app.emit_to(target.as_str(), "document-opened", &state);
+        }
+        MenuCommand::OpenDocument => {
+            let target = target_window(app);
+            let handle = app.clone();
+            document_dialog(app).pick_file(move |file| {
+                if let Some(path) = file.and_then(|file| file.into_path().ok()) {
+                    let result = open_document_file(&handle, &target, &path);
+                    emit_document_result(&handle, &target, "document-opened", result);
+                }
+            });
+        }
+        MenuCommand::SaveDocument | MenuCommand::SaveDocumentAs => {
+            let target = target_window(app);
+            let documents = app.state::<Documents>();
+            let Some(id) = documents.active(&target) else {
+                eprintln!("[RUST] 窗口 {} 没有正在编辑的文档", target);
+                return Ok(());
+            };
+            let info = documents.info(id)?;
+            // 已有文件的文档直接保存，新建的文档和“另存为”先选择保存位置
+            match (&command, &info.path) {
+                (MenuCommand::SaveDocument, Some(_)) => {
+                    let result = save_document_file(app, id, None);
+                    emit_document_result(app, &target, "document-saved", result);
+                }
+                _ => {
+                    let file_name = info
+                        .path
+                        .as_ref()
+                        .and_then(|path| path.file_name())
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| "untitled.txt".to_string());
+                    let handle = app.clone();
+                    document_dialog(app).set_file_name(file_name).save_file(move |file| {
+                        if let Some(path) = file.and_then(|file| file.into_path().ok()) {
+                            let result = save_document_file(&handle, id, Some(path));
+                            emit_document_result(&handle, &target, "document-saved", result);
+                        }
+                    });
+                }
+            }
+        }
+        MenuCommand::ClearRecent => {
+            app.state::<RecentFiles>().clear();
+            refresh_menu(app);
+        }
+        MenuCommand::ZoomIn | MenuCommand::ZoomOut | MenuCommand::ZoomReset => {
+            let step = match command {
+                MenuCommand::ZoomIn => ZoomStep::In,
+                MenuCommand::ZoomOut => ZoomStep::Out,
+                _ => ZoomStep::Reset,
+            };
+            let level = app.state::<ZoomSettings>().step(&target_window(app), step);
+            apply_zoom(app, &level);
+        }
+        MenuCommand::Undo => change_active_document(app, |documents, id| documents.undo(id))?,
+        MenuCommand::Redo => change_active_document(app, |documents, id| documents.redo(id))?,
+        MenuCommand::ConvertLineEndings(line_ending) => {
+            change_active_document(app, |documents, id| documents.convert_line_ending(id, line_ending))?
+        }
+        MenuCommand::TrimTrailingWhitespace => change_active_document(app, Documents::trim_trailing_whitespace)?,
+        MenuCommand::EnsureFinalNewline => change_active_document(app, Documents::ensure_final_newline)?,
+        MenuCommand::Cut => copy_selection(app, true, source)?,
+        MenuCommand::Copy => copy_selection(app, false, source)?,
+        MenuCommand::Paste => {
+            // 由 Rust 读取剪贴板，网页自身无法读取剪贴板的场合（例如从托盘或命令面板触发）也能粘贴
+            let clipboard = app.state::<ClipboardService>().read_any()?;
+            emit_menu_action(app, MenuActionEvent {
+                clipboard,
+                ..MenuActionEvent::new(MenuAction::Paste, source)
+            });
+        }
+        MenuCommand::ClearHistory => {
+            app.state::<HistoryStore>().clear();
+            let _ = app.emit("history-cleared", ());
+        }
+        MenuCommand::ClearClipboardHistory => app.state::<ClipboardService>().clear_history(),
+        MenuCommand::ResetKeyBindings => {
+            app.state::<Keymap>().reset(None)?;
+            refresh_menu(app);
+        }
+        MenuCommand::Quit => {
+            eprintln!("[RUST] 菜单：退出应用");
+            autosave_now(app);
+            std::process::exit(0);
+        }
+        MenuCommand::ShowWindow => show_main_window(app),
+        MenuCommand::HideWindow => hide_main_window(app),
+        MenuCommand::SetLocale(locale) => {
+            change_locale(app, locale)?;
+        }
+    }
+    Ok(())
+}
+
+// 处理菜单事件：判断触发方式后交给 dispatch_action
+// 注意 tauri 中托盘菜单的事件同样会送到全局菜单监听器，所以只需要注册一次
+fn dispatch_menu_event(app: &AppHandle, id: &str) {
+    use tauri::Manager;
+
+    let source = if app.state::<MenuRegistry>().is_tray_item(id) {
+        ActionSource::Tray
+    } else if focused_window(app).is_some_and(|label| app.state::<FocusTracker>().modifiers_held(&label)) {
+        ActionSource::Accelerator
+    } else {
+        ActionSource::Click
+    };
+    if let Err(e) = dispatch_action(app, id, source) {
+        eprintln!("[RUST] 执行菜单项 {} 失败: {}", id, e);
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     use tauri::Manager;
-    use tauri::Emitter;
-    use tauri::menu::{Menu, MenuItem, Submenu};
     use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
     
     tauri::Builder::default()
         .setup(|app| {
-            // ========== 创建应用窗口菜单栏 ==========
-            // 文件菜单
-            let new_item = MenuItem::with_id(app, "new", "新建", true, Some("CmdOrCtrl+N"))?;
-            let open_item = MenuItem::with_id(app, "open", "打开", true, Some("CmdOrCtrl+O"))?;
-            let save_item = MenuItem::with_id(app, "save", "保存", true, Some("CmdOrCtrl+S"))?;
-            let save_as_item = MenuItem::with_id(app, "save_as", "另存为", true, None::<&str>)?;
-            let quit_app_item = MenuItem::with_id(app, "quit_app", "退出", true, Some("CmdOrCtrl+Q"))?;
-            
-            let file_menu = Submenu::with_items(
-                app,
-                "文件",
-                true,
-                &[&new_item, &open_item, &save_item, &save_as_item, &quit_app_item],
-            )?;
-            
-            // 编辑菜单
-            let undo_item = MenuItem::with_id(app, "undo", "撤销", true, Some("CmdOrCtrl+Z"))?;
-            let redo_item = MenuItem::with_id(app, "redo", "重做", true, Some("CmdOrCtrl+Shift+Z"))?;
-            let cut_item = MenuItem::with_id(app, "cut", "剪切", true, Some("CmdOrCtrl+X"))?;
-            let copy_item = MenuItem::with_id(app, "copy", "复制", true, Some("CmdOrCtrl+C"))?;
-            let paste_item = MenuItem::with_id(app, "paste", "粘贴", true, Some("CmdOrCtrl+V"))?;
-            
-            let edit_menu = Submenu::with_items(
-                app,
-                "编辑",
-                true,
-                &[&undo_item, &redo_item, &cut_item, &copy_item, &paste_item],
-            )?;
-            
-            // 视图菜单
-            let zoom_in_item = MenuItem::with_id(app, "zoom_in", "放大", true, Some("CmdOrCtrl+Plus"))?;
-            let zoom_out_item = MenuItem::with_id(app, "zoom_out", "缩小", true, Some("CmdOrCtrl+-"))?;
-            let zoom_reset_item = MenuItem::with_id(app, "zoom_reset", "重置缩放", true, Some("CmdOrCtrl+0"))?;
+            // 计算历史保存在应用数据目录下，重启后仍然可用
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(HistoryStore::load(data_dir.join("history.json")));
+            app.manage(StreamSessions::default());
             
-            let view_menu = Submenu::with_items(
-                app,
-                "视图",
-                true,
-                &[&zoom_in_item, &zoom_out_item, &zoom_reset_item],
-            )?;
+            // 界面语言：优先使用保存的设置，否则跟随系统
+            app.manage(LocaleSettings::load(data_dir.join("locale.json")));
             
-            // 帮助菜单
-            let about_item = MenuItem::with_id(app, "about", "关于", true, None::<&str>)?;
+            // 每个窗口的缩放比例，页面加载完成时生效（见下面的 on_page_load）
+            app.manage(ZoomSettings::load(data_dir.join("zoom.json")));
             
-            let help_menu = Submenu::with_items(
-                app,
-                "帮助",
-                true,
-                &[&about_item],
-            )?;
+            // 剪贴板服务，“粘贴”菜单项和剪贴板命令共用
+            app.manage(ClipboardService::system());
             
-            // 创建主菜单
-            let app_menu = Menu::with_items(app, &[&file_menu, &edit_menu, &view_menu, &help_menu])?;
+            // 最近打开的文件，显示在“文件 → 最近打开”子菜单中
+            app.manage(RecentFiles::load(data_dir.join("recent.json")));
             
-            // 设置应用菜单
+            // ========== 创建应用窗口菜单栏和托盘菜单 ==========
+            // 菜单结构来自 menus/menu.json（可以用应用配置目录下的 menu.json 覆盖），
+            // 文字来自 i18n 消息目录，切换语言时由 menu::refresh 重建
+            let config_dir = app.path().app_config_dir()?;
+            let menu_config = MenuConfig::load(&config_dir.join("menu.json"));
+            // 快捷键：menu.json 中为默认值，应用配置目录下的 keymap.json 中为用户的设置
+            app.manage(Keymap::load(config_dir.join("keymap.json"), menu_config.menu_items()));
+            app.manage(MenuRegistry::new(menu_config));
+            let (app_menu, tray_menu) = menu::build(app.handle())?;
             app.set_menu(app_menu)?;
             
-            // 监听应用菜单事件
-            app.on_menu_event(|app, event| {
-                match event.id.as_ref() {
-                    "new" => {
-                        eprintln!("[RUST] 菜单：新建文件");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "new");
-                        }
-                    }
-                    "open" => {
-                        eprintln!("[RUST] 菜单：打开文件");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "open");
-                        }
-                    }
-                    "save" => {
-                        eprintln!("[RUST] 菜单：保存文件");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "save");
-                        }
-                    }
-                    "save_as" => {
-                        eprintln!("[RUST] 菜单：另存为");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "save_as");
-                        }
-                    }
-                    "quit_app" => {
-                        eprintln!("[RUST] 菜单：退出应用");
-                        std::process::exit(0);
-                    }
-                    "undo" => {
-                        eprintln!("[RUST] 菜单：撤销");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "undo");
-                        }
-                    }
-                    "redo" => {
-                        eprintln!("[RUST] 菜单：重做");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "redo");
-                        }
-                    }
-                    "cut" => {
-                        eprintln!("[RUST] 菜单：剪切");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "cut");
-                        }
-                    }
-                    "copy" => {
-                        eprintln!("[RUST] 菜单：复制");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "copy");
-                        }
-                    }
-                    "paste" => {
-                        eprintln!("[RUST] 菜单：粘贴");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "paste");
-                        }
-                    }
-                    "zoom_in" => {
-                        eprintln!("[RUST] 菜单：放大");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "zoom_in");
-                        }
-                    }
-                    "zoom_out" => {
-                        eprintln!("[RUST] 菜单：缩小");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "zoom_out");
-                        }
-                    }
-                    "zoom_reset" => {
-                        eprintln!("[RUST] 菜单：重置缩放");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "zoom_reset");
-                        }
-                    }
-                    "about" => {
-                        eprintln!("[RUST] 菜单：关于");
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("menu-action", "about");
-                        }
-                    }
-                    _ => {
-                        eprintln!("[RUST] 未知菜单项: {:?}", event.id);
-                    }
-                }
-            });
+            // 编辑器中的文档，撤销 / 重做菜单项的启用状态随焦点窗口的文档变化；
+            // 撤销步数和备份个数保存在应用配置目录下
+            app.manage(Documents::load(config_dir.join("documents.json")));
             
-            // ========== 创建系统托盘菜单 ==========
-            let show_item = MenuItem::with_id(app, "show", "显示窗口", true, None::<&str>)?;
-            let hide_item = MenuItem::with_id(app, "hide", "隐藏窗口", true, None::<&str>)?;
-            let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+            // 自动保存：未保存的修改定时写入应用数据目录下的 recovery 目录，
+            // 上次运行留下的快照通过 list_recovery_snapshots 列出
+            app.manage(Autosave::new(data_dir.join("recovery")));
+            let handle = app.handle().clone();
+            std::thread::spawn(move || run_autosave(handle));
             
-            // 创建托盘菜单
-            let tray_menu = Menu::with_items(app, &[&show_item, &hide_item, &quit_item])?;
+            // 监听菜单事件（包括托盘菜单），按分发表执行对应的动作
+            app.on_menu_event(|app, event| dispatch_menu_event(app, event.id.as_ref()));
             
-            // 创建系统托盘图标
-            let _tray = TrayIconBuilder::new()
+            // 创建系统托盘图标（带 ID，切换语言时通过 ID 找到托盘重建菜单）
+            let _tray = TrayIconBuilder::with_id(menu::TRAY_ID)
                 .menu(&tray_menu)
-                .tooltip("我的TAURI学习应用")
+                .tooltip(i18n::t("tray.tooltip"))
                 .show_menu_on_left_click(true)  // 左键点击也显示菜单
-                .on_menu_event(move |app, event| {
-                    // 处理菜单项点击事件
-                    eprintln!("[RUST] 托盘菜单事件: {:?}", event.id);
-                    match event.id.as_ref() {
-                        "show" => {
-                            eprintln!("[RUST] 显示窗口菜单项被点击");
-                            // 显示主窗口 - 尝试多种方法获取窗口
-                            let window = app.get_webview_window("main")
-                                .or_else(|| {
-                                    eprintln!("[RUST] get_webview_window 失败，尝试 webview_windows");
-                                    let all_windows = app.webview_windows();
-                                    let window_labels: Vec<String> = all_windows.keys().cloned().collect();
-                                    eprintln!("[RUST] 所有可用窗口标签: {:?}", window_labels);
-                                    all_windows.get("main").cloned()
-                                });
-                            
-                            if let Some(window) = window {
-                                eprintln!("[RUST] ✅ 成功获取主窗口");
-                                
-                                // 检查窗口当前状态
-                                if let Ok(is_visible) = window.is_visible() {
-                                    eprintln!("[RUST] 窗口当前可见状态: {}", is_visible);
-                                }
-                                
-                                // 检查窗口是否最小化
-                                if let Ok(is_minimized) = window.is_minimized() {
-                                    eprintln!("[RUST] 窗口是否最小化: {}", is_minimized);
-                                    if is_minimized {
-                                        // 先取消最小化
-                                        match window.unminimize() {
-                                            Ok(_) => eprintln!("[RUST] 取消最小化成功"),
-                                            Err(e) => eprintln!("[RUST] 取消最小化失败: {:?}", e),
-                                        }
-                                    }
-                                }
-                                
-                                // 显示窗口 - 使用异步方式，确保窗口真正显示
-                                match window.show() {
-                                    Ok(_) => {
-                                        eprintln!("[RUST] 窗口显示命令执行成功");
-                                        
-                                        // 等待一小段时间确保窗口显示
-                                        std::thread::sleep(std::time::Duration::from_millis(200));
-                                        
-                                        // 再次检查窗口状态
-                                        if let Ok(is_visible) = window.is_visible() {
-                                            eprintln!("[RUST] 显示后窗口可见状态: {}", is_visible);
-                                            
-                                            // 如果窗口仍然不可见，尝试其他方法
-                                            if !is_visible {
-                                                eprintln!("[RUST] ⚠️ 窗口显示后仍然不可见，尝试强制显示");
-                                                
-                                                // 尝试将窗口居中（确保窗口在可见区域）
-                                                if let Err(e) = window.center() {
-                                                    eprintln!("[RUST] 窗口居中失败: {:?}", e);
-                                                } else {
-                                                    eprintln!("[RUST] 窗口已居中");
-                                                }
-                                                
-                                                // 再次尝试显示
-                                                if let Err(e) = window.show() {
-                                                    eprintln!("[RUST] 再次显示窗口失败: {:?}", e);
-                                                } else {
-                                                    eprintln!("[RUST] 再次显示窗口成功");
-                                                    std::thread::sleep(std::time::Duration::from_millis(100));
-                                                }
-                                            }
-                                        }
-                                        
-                                        // 设置焦点
-                                        match window.set_focus() {
-                                            Ok(_) => eprintln!("[RUST] 窗口焦点设置成功"),
-                                            Err(e) => eprintln!("[RUST] 窗口焦点设置失败: {:?}", e),
-                                        }
-                                        
-                                        // 最终检查窗口状态
-                                        std::thread::sleep(std::time::Duration::from_millis(100));
-                                        if let Ok(is_visible) = window.is_visible() {
-                                            eprintln!("[RUST] 窗口最终可见状态: {}", is_visible);
-                                        }
-                                    }
-                                    Err(e) => eprintln!("[RUST] ❌ 窗口显示失败: {:?}", e),
-                                }
-                            } else {
-                                eprintln!("[RUST] ❌ 无法获取主窗口（标签：main）");
-                                // 尝试列出所有窗口
-                                let all_windows = app.webview_windows();
-                                let window_labels: Vec<String> = all_windows.keys().cloned().collect();
-                                eprintln!("[RUST] 所有可用窗口标签: {:?}", window_labels);
-                                
-                                // 如果主窗口不存在，尝试重新创建（作为最后手段）
-                                eprintln!("[RUST] ⚠️ 主窗口不存在，可能需要重新创建");
-                            }
-                        }
-                        "hide" => {
-                            eprintln!("[RUST] 隐藏窗口菜单项被点击");
-                            // 隐藏主窗口
-                            if let Some(window) = app.get_webview_window("main") {
-                                match window.hide() {
-                                    Ok(_) => eprintln!("[RUST] 窗口隐藏成功"),
-                                    Err(e) => eprintln!("[RUST] 窗口隐藏失败: {:?}", e),
-                                }
-                            } else {
-                                eprintln!("[RUST] ❌ 无法获取主窗口（标签：main）");
-                            }
-                        }
-                        "quit" => {
-                            eprintln!("[RUST] 退出菜单项被点击");
-                            // 退出应用
-                            std::process::exit(0);
-                        }
-                        _ => {
-                            eprintln!("[RUST] 未知菜单项: {:?}", event.id);
-                        }
-                    }
-                })
                 .on_tray_icon_event(|tray, event| {
                     // 处理托盘图标事件（点击、双击等）
-                    match event {
-                        TrayIconEvent::Click {
-                            button: MouseButton::Left,
-                            button_state: MouseButtonState::Up,
-                            ..
-                        } => {
-                            // 左键点击：显示/隐藏窗口
-                            let app = tray.app_handle();
-                            if let Some(window) = app.get_webview_window("main") {
-                                if window.is_visible().unwrap_or(false) {
-                                    let _ = window.hide();
-                                } else {
-                                    let _ = window.unminimize();
-                                    let _ = window.show();
-                                    let _ = window.set_focus();
-                                }
+                    if let TrayIconEvent::Click {
+                        button: MouseButton::Left,
+                        button_state: MouseButtonState::Up,
+                        ..
+                    } = event
+                    {
+                        // 左键点击：显示/隐藏窗口
+                        let app = tray.app_handle();
+                        if let Some(window) = app.get_webview_window("main") {
+                            if window.is_visible().unwrap_or(false) {
+                                let _ = window.hide();
+                            } else {
+                                let _ = window.unminimize();
+                                let _ = window.show();
+                                let _ = window.set_focus();
                             }
                         }
-                        _ => {}
                     }
                 })
                 .build(app)?;
             
             Ok(())
         })
+        // 页面加载完成（包括刷新）后恢复该窗口保存的缩放比例
+        .on_page_load(|webview, payload| {
+            if payload.event() == tauri::webview::PageLoadEvent::Finished {
+                if let Some(zoom) = webview.try_state::<ZoomSettings>() {
+                    apply_zoom(webview.app_handle(), &zoom.level(webview.label()));
+                }
+            }
+        })
+        // 窗口事件可能早于 setup 到达，焦点状态在创建应用时就注册
+        .manage(FocusTracker::default())
         .on_window_event(|window, event| {
+            // 记录窗口焦点，菜单动作发给拥有焦点的窗口
+            match event {
+                tauri::WindowEvent::Focused(focused) => {
+                    window.state::<FocusTracker>().set_focused(window.label(), *focused);
+                    if *focused {
+                        refresh_edit_menu(window.app_handle());
+                    }
+                }
+                tauri::WindowEvent::Destroyed => {
+                    window.state::<FocusTracker>().forget(window.label());
+                }
+                _ => {}
+            }
+            
             // 处理窗口关闭事件：隐藏窗口而不是退出应用
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                // 窗口关闭（或隐藏）前先把未保存的修改写入恢复快照
+                autosave_now(window.app_handle());
+                
                 let window_label = window.label().to_string();
                 eprintln!("[RUST] ========== 窗口关闭请求 ==========");
                 eprintln!("[RUST] 窗口标签: {:?}", window_label);
@@ -458,7 +1334,7 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())      // 注册文件系统插件
         .plugin(tauri_plugin_dialog::init())  // 注册对话框插件
         .plugin(tauri_plugin_notification::init())  // 注册通知插件
-        .invoke_handler(tauri::generate_handler![greet, calculate, get_timestamp, get_system_info, process_numbers, safe_divide])
+        .invoke_handler(tauri::generate_handler![greet, calculate, get_timestamp, get_system_info, process_numbers, safe_divide, evaluate_expression, calculate_decimal, list_history, search_history, delete_history_entry, clear_history, replay_history_entry, export_history, open_stats_session, push_stats_batch, get_session_statistics, subscribe_stats_session, close_stats_session, analyze_csv, compute_histogram, fit_regression, predict_regression, detect_outliers, set_locale, get_locale, report_modifiers, get_menu_item, update_menu_items, add_recent_file, list_recent_files, clear_recent_files, set_recent_files_limit, list_key_bindings, set_key_binding, reset_key_bindings, search_actions, list_actions, execute_action, get_zoom, set_zoom, set_zoom_range, read_clipboard_text, write_clipboard_text, read_clipboard_html, write_clipboard_html, read_clipboard_image, write_clipboard_image, list_clipboard_history, paste_clipboard_entry, clear_clipboard_history, set_clipboard_history_limit, create_document, get_document, edit_document, undo_document, redo_document, mark_document_saved, set_undo_depth, get_document_settings, set_backup_count, set_autosave_interval, list_recovery_snapshots, recover_snapshot, discard_recovery_snapshot, clear_recovery_snapshots, open_document, save_document, save_document_as, close_document, list_documents, set_document_selection, convert_document_encoding, convert_line_endings, trim_trailing_whitespace, ensure_final_newline])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
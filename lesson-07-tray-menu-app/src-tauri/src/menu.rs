@@ -0,0 +1,710 @@
+// 声明式菜单：应用菜单栏和托盘菜单的结构写在 menus/menu.json 中，
+// 通用的构建器据此生成 tauri 菜单，同时生成“菜单项 ID -> 动作”的分发表
+// 新增菜单项只需要修改配置文件（和消息目录），不需要改动 Rust 代码
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::menu::{
+    CheckMenuItem, IsMenuItem, Menu, MenuItem, MenuItemKind, PredefinedMenuItem, Submenu,
+};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::clipboard::ClipboardContent;
+use crate::document::LineEnding;
+use crate::error::{CommandError, Resource};
+use crate::i18n::{t, Locale, LocaleSettings};
+use crate::keymap::Keymap;
+use crate::recent::RecentFiles;
+
+// 系统托盘图标的 ID，重建托盘菜单时通过它找到托盘
+pub const TRAY_ID: &str = "main-tray";
+
+// 编译时嵌入的默认菜单配置
+const DEFAULT_CONFIG: &str = include_str!("../menus/menu.json");
+
+// 点击菜单项（或按下快捷键）时执行的动作
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MenuCommand {
+    // 向当前窗口发送 "menu-action" 事件（载荷为 MenuActionEvent），由前端处理（默认）
+    #[default]
+    Emit,
+    // 退出应用
+    Quit,
+    // 显示 / 隐藏主窗口
+    ShowWindow,
+    HideWindow,
+    // 切换界面语言，null 表示跟随系统
+    SetLocale(Option<Locale>),
+    // 新建 / 打开 / 保存 / 另存为当前窗口的文档（见 document.rs），需要时由 Rust 弹出文件对话框
+    NewDocument,
+    OpenDocument,
+    SaveDocument,
+    SaveDocumentAs,
+    // 清空最近打开的文件
+    ClearRecent,
+    // 放大 / 缩小 / 重置当前窗口的缩放（见 zoom.rs）
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    // 撤销 / 重做当前窗口正在编辑的文档（见 document.rs）
+    Undo,
+    Redo,
+    // 整理当前窗口正在编辑的文档：转换换行符风格、删除行尾空白、补上末尾换行，各自作为一步撤销
+    ConvertLineEndings(LineEnding),
+    TrimTrailingWhitespace,
+    EnsureFinalNewline,
+    // 把当前窗口文档中选中的文字写入剪贴板，剪切时再删除这段文字（作为一步撤销）；
+    // 文档中没有选中文字时退回到 "menu-action" 事件，由前端处理输入框和页面中选中的文字
+    Cut,
+    Copy,
+    // 读取剪贴板，随 "menu-action" 事件发给当前窗口粘贴（见 clipboard.rs）
+    Paste,
+    // 清空计算历史
+    ClearHistory,
+    // 清空剪贴板历史
+    ClearClipboardHistory,
+    // 恢复全部默认快捷键
+    ResetKeyBindings,
+    // 打开最近打开的文件；由 recentFiles 节点生成，不能写在配置文件中
+    #[serde(skip_deserializing)]
+    OpenRecent(PathBuf),
+}
+
+// 由前端处理的菜单动作；序列化为菜单项 ID（如 "save_as"），
+// 配置文件中新增的菜单项对应 Custom，前端按 ID 处理即可
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum MenuAction {
+    New,
+    Open,
+    Save,
+    SaveAs,
+    Undo,
+    Redo,
+    Cut,
+    Copy,
+    Paste,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    About,
+    Custom(String),
+}
+
+impl MenuAction {
+    pub fn id(&self) -> &str {
+        match self {
+            MenuAction::New => "new",
+            MenuAction::Open => "open",
+            MenuAction::Save => "save",
+            MenuAction::SaveAs => "save_as",
+            MenuAction::Undo => "undo",
+            MenuAction::Redo => "redo",
+            MenuAction::Cut => "cut",
+            MenuAction::Copy => "copy",
+            MenuAction::Paste => "paste",
+            MenuAction::ZoomIn => "zoom_in",
+            MenuAction::ZoomOut => "zoom_out",
+            MenuAction::ZoomReset => "zoom_reset",
+            MenuAction::About => "about",
+            MenuAction::Custom(id) => id,
+        }
+    }
+
+    pub fn from_id(id: &str) -> Self {
+        match id {
+            "new" => MenuAction::New,
+            "open" => MenuAction::Open,
+            "save" => MenuAction::Save,
+            "save_as" => MenuAction::SaveAs,
+            "undo" => MenuAction::Undo,
+            "redo" => MenuAction::Redo,
+            "cut" => MenuAction::Cut,
+            "copy" => MenuAction::Copy,
+            "paste" => MenuAction::Paste,
+            "zoom_in" => MenuAction::ZoomIn,
+            "zoom_out" => MenuAction::ZoomOut,
+            "zoom_reset" => MenuAction::ZoomReset,
+            "about" => MenuAction::About,
+            _ => MenuAction::Custom(id.to_string()),
+        }
+    }
+}
+
+impl From<String> for MenuAction {
+    fn from(id: String) -> Self {
+        MenuAction::from_id(&id)
+    }
+}
+
+impl From<MenuAction> for String {
+    fn from(action: MenuAction) -> Self {
+        action.id().to_string()
+    }
+}
+
+// 菜单动作的触发方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionSource {
+    // 在应用菜单栏中点击
+    Click,
+    // 按下菜单项的快捷键
+    Accelerator,
+    // 在托盘菜单中点击
+    Tray,
+    // 通过命令面板执行（execute_action 命令）
+    Palette,
+}
+
+// "menu-action" 事件的载荷
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuActionEvent {
+    pub action: MenuAction,
+    // 触发动作时拥有焦点的窗口；从托盘触发且没有窗口拥有焦点时为 None
+    pub window: Option<String>,
+    pub source: ActionSource,
+    // 粘贴时为剪贴板中的内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clipboard: Option<ClipboardContent>,
+    // Unix 时间戳（秒）
+    pub timestamp: u64,
+}
+
+impl MenuActionEvent {
+    // 不带附加内容的事件，window 在发送时填写
+    pub fn new(action: MenuAction, source: ActionSource) -> Self {
+        MenuActionEvent {
+            action,
+            window: None,
+            source,
+            clipboard: None,
+            timestamp: crate::current_timestamp(),
+        }
+    }
+}
+
+// 菜单树节点的类型，省略时为普通菜单项
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeKind {
+    #[default]
+    Item,
+    Separator,
+    Submenu,
+    // 占位节点，构建时展开为最近打开的文件列表（见 recent.rs）
+    RecentFiles,
+}
+
+// 菜单树中的一个节点；不同类型用到的字段不同，多余的字段会被忽略
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuNode {
+    #[serde(default, rename = "type")]
+    pub kind: NodeKind,
+    // 菜单项必须有 ID，子菜单可选
+    pub id: Option<String>,
+    // label 为消息目录中的键，会随界面语言变化；text 为不需要翻译的原文
+    pub label: Option<String>,
+    pub text: Option<String>,
+    pub accelerator: Option<String>,
+    // 命令面板中显示的说明，为消息目录中的键
+    pub description: Option<String>,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    // 可勾选的菜单项；切换语言的菜单项会根据当前设置自动勾选
+    #[serde(default)]
+    pub checkable: bool,
+    #[serde(default)]
+    pub checked: bool,
+    #[serde(default)]
+    pub action: MenuCommand,
+    // 子菜单中的菜单项
+    #[serde(default)]
+    pub items: Vec<MenuNode>,
+}
+
+impl MenuNode {
+    // 当前语言下显示的文字
+    pub fn text(&self) -> String {
+        match (&self.label, &self.text) {
+            (Some(key), _) => t(key),
+            (None, Some(text)) => text.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+// 整个配置文件：menu 为应用菜单栏，tray 为托盘菜单，
+// commands 为不出现在菜单中、只能通过命令面板执行的命令
+#[derive(Debug, Clone, Deserialize)]
+pub struct MenuConfig {
+    pub menu: Vec<MenuNode>,
+    pub tray: Vec<MenuNode>,
+    #[serde(default)]
+    pub commands: Vec<MenuNode>,
+}
+
+impl MenuConfig {
+    pub fn parse(text: &str) -> Result<Self, CommandError> {
+        let config: MenuConfig =
+            serde_json::from_str(text).map_err(|e| parse_error(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // 优先加载 path 处的用户配置，不存在或无效时使用内置的默认配置
+    pub fn load(path: &Path) -> Self {
+        if let Ok(text) = fs::read_to_string(path) {
+            match MenuConfig::parse(&text) {
+                Ok(config) => {
+                    eprintln!("[RUST] 已加载菜单配置: {}", path.display());
+                    return config;
+                }
+                Err(e) => eprintln!("[RUST] 菜单配置无效，使用默认菜单: {}", e),
+            }
+        }
+        MenuConfig::parse(DEFAULT_CONFIG).expect("内置菜单配置无效")
+    }
+
+    // 应用菜单栏中的全部菜单项（不含分隔线和子菜单本身），按显示顺序排列
+    pub fn menu_items(&self) -> Vec<MenuNode> {
+        fn visit(nodes: &[MenuNode], items: &mut Vec<MenuNode>) {
+            for node in nodes {
+                match node.kind {
+                    NodeKind::Item => items.push(node.clone()),
+                    NodeKind::Submenu => visit(&node.items, items),
+                    NodeKind::Separator | NodeKind::RecentFiles => {}
+                }
+            }
+        }
+
+        let mut items = Vec::new();
+        visit(&self.menu, &mut items);
+        items
+    }
+
+    // 检查菜单项是否都有 ID、ID 是否重复、文字是否缺失
+    fn validate(&self) -> Result<(), CommandError> {
+        fn visit(nodes: &[MenuNode], ids: &mut HashSet<String>) -> Result<(), CommandError> {
+            for node in nodes {
+                if matches!(node.kind, NodeKind::Separator | NodeKind::RecentFiles) {
+                    continue;
+                }
+                if node.kind == NodeKind::Item && node.id.is_none() {
+                    return Err(parse_error(format!(
+                        "菜单项缺少 id: {}",
+                        node.label
+                            .as_deref()
+                            .or(node.text.as_deref())
+                            .unwrap_or("?")
+                    )));
+                }
+                if let Some(id) = &node.id {
+                    if !ids.insert(id.clone()) {
+                        return Err(parse_error(format!("菜单项 ID 重复: {}", id)));
+                    }
+                }
+                if node.label.is_none() && node.text.is_none() {
+                    return Err(parse_error(format!(
+                        "菜单项缺少 label 或 text: {}",
+                        node.id.as_deref().unwrap_or("?")
+                    )));
+                }
+                visit(&node.items, ids)?;
+            }
+            Ok(())
+        }
+
+        let mut ids = HashSet::new();
+        visit(&self.menu, &mut ids)?;
+        visit(&self.tray, &mut ids)?;
+        if let Some(node) = self
+            .commands
+            .iter()
+            .find(|node| node.kind != NodeKind::Item)
+        {
+            return Err(parse_error(format!(
+                "commands 中只能有菜单项: {}",
+                node.id.as_deref().unwrap_or("?")
+            )));
+        }
+        visit(&self.commands, &mut ids)
+    }
+}
+
+fn parse_error(reason: String) -> CommandError {
+    CommandError::ParseFailed {
+        format: "menu".to_string(),
+        reason,
+    }
+}
+
+// 菜单项的运行时状态：是否启用、显示的文字、勾选状态
+// 用于修改时字段为 None 表示保持不变
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuItemState {
+    pub enabled: Option<bool>,
+    pub text: Option<String>,
+    pub checked: Option<bool>,
+}
+
+impl MenuItemState {
+    // 用 update 中给出的字段覆盖当前状态
+    fn merge(&mut self, update: &MenuItemState) {
+        if update.enabled.is_some() {
+            self.enabled = update.enabled;
+        }
+        if update.text.is_some() {
+            self.text = update.text.clone();
+        }
+        if update.checked.is_some() {
+            self.checked = update.checked;
+        }
+    }
+}
+
+// 批量修改时的一项
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuItemUpdate {
+    pub id: String,
+    #[serde(flatten)]
+    pub state: MenuItemState,
+}
+
+// 菜单配置和分发表
+pub struct MenuRegistry {
+    config: MenuConfig,
+    commands: RwLock<HashMap<String, MenuCommand>>,
+    // 托盘菜单中的菜单项 ID，用于区分动作来自托盘还是应用菜单栏
+    tray_items: RwLock<HashSet<String>>,
+    // 运行时修改过的菜单项状态，切换语言重建菜单后会重新应用
+    overrides: RwLock<HashMap<String, MenuItemState>>,
+}
+
+impl MenuRegistry {
+    pub fn new(config: MenuConfig) -> Self {
+        MenuRegistry {
+            config,
+            commands: RwLock::new(HashMap::new()),
+            tray_items: RwLock::new(HashSet::new()),
+            overrides: RwLock::new(HashMap::new()),
+        }
+    }
+
+    // 查找菜单项对应的动作
+    pub fn command(&self, id: &str) -> Option<MenuCommand> {
+        self.commands.read().unwrap().get(id).cloned()
+    }
+
+    pub fn config(&self) -> &MenuConfig {
+        &self.config
+    }
+
+    // 当前“最近打开”子菜单中的菜单项 ID 和对应的文件，按显示顺序排列
+    pub fn recent_files(&self) -> Vec<(String, PathBuf)> {
+        let mut recent: Vec<(String, PathBuf)> = self
+            .commands
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|(id, command)| match command {
+                MenuCommand::OpenRecent(path) => Some((id.clone(), path.clone())),
+                _ => None,
+            })
+            .collect();
+        recent.sort_by_key(|(id, _)| {
+            id.trim_start_matches("recent:")
+                .parse::<usize>()
+                .unwrap_or(usize::MAX)
+        });
+        recent
+    }
+
+    pub fn is_tray_item(&self, id: &str) -> bool {
+        self.tray_items.read().unwrap().contains(id)
+    }
+}
+
+// 已构建的菜单项句柄（按 ID 索引），用于在运行时修改菜单项
+// 每次重建菜单都会整体替换；第一次构建菜单时注册为全局状态
+pub struct MenuHandles<R: Runtime> {
+    items: RwLock<HashMap<String, MenuItemKind<R>>>,
+}
+
+type Append<'m, R> = dyn FnMut(&dyn IsMenuItem<R>) -> tauri::Result<()> + 'm;
+
+// 菜单构建过程中的上下文：收集分发表，并知道当前的语言设置以决定勾选状态
+struct Builder<'a, R: Runtime> {
+    app: &'a AppHandle<R>,
+    locale: Option<Locale>,
+    commands: HashMap<String, MenuCommand>,
+    // 正在构建托盘菜单时为 true，此时构建的菜单项 ID 记入 tray_items
+    in_tray: bool,
+    tray_items: HashSet<String>,
+    overrides: HashMap<String, MenuItemState>,
+    handles: HashMap<String, MenuItemKind<R>>,
+    // 快捷键映射中的快捷键（见 keymap.rs），托盘菜单项不在其中，使用配置中的值
+    accelerators: HashMap<String, Option<String>>,
+}
+
+impl<R: Runtime> Builder<'_, R> {
+    // 把 nodes 逐个构建出来交给 append（追加到菜单或子菜单中），子菜单递归处理
+    fn append_to(&mut self, nodes: &[MenuNode], append: &mut Append<R>) -> tauri::Result<()> {
+        for node in nodes {
+            match node.kind {
+                NodeKind::Separator => append(&PredefinedMenuItem::separator(self.app)?)?,
+                NodeKind::RecentFiles => {
+                    let paths = self.app.state::<RecentFiles>().paths();
+                    if paths.is_empty() {
+                        append(&MenuItem::with_id(
+                            self.app,
+                            "recent:empty",
+                            t("menu.recentEmpty"),
+                            false,
+                            None::<&str>,
+                        )?)?;
+                    }
+                    for (index, path) in paths.into_iter().enumerate() {
+                        let id = format!("recent:{}", index);
+                        let text = recent_label(index, &path);
+                        self.commands
+                            .insert(id.clone(), MenuCommand::OpenRecent(path));
+                        append(&MenuItem::with_id(self.app, id, text, true, None::<&str>)?)?;
+                    }
+                }
+                NodeKind::Submenu => {
+                    let state = self.state(node);
+                    let text = state.text.unwrap_or_else(|| node.text());
+                    let enabled = state.enabled.unwrap_or(node.enabled);
+                    let submenu = match &node.id {
+                        Some(id) => Submenu::with_id(self.app, id, text, enabled)?,
+                        None => Submenu::new(self.app, text, enabled)?,
+                    };
+                    self.append_to(&node.items, &mut |item| submenu.append(item))?;
+                    append(&submenu)?;
+                    if let Some(id) = &node.id {
+                        self.handles
+                            .insert(id.clone(), MenuItemKind::Submenu(submenu));
+                    }
+                }
+                NodeKind::Item => {
+                    // validate 已保证菜单项都有 ID
+                    let id = node.id.clone().unwrap_or_default();
+                    self.commands.insert(id.clone(), node.action.clone());
+                    if self.in_tray {
+                        self.tray_items.insert(id.clone());
+                    }
+                    let state = self.state(node);
+                    let text = state.text.unwrap_or_else(|| node.text());
+                    let enabled = state.enabled.unwrap_or(node.enabled);
+                    let accelerator = match self.accelerators.get(&id) {
+                        Some(accelerator) => accelerator.as_deref(),
+                        None => node.accelerator.as_deref(),
+                    };
+                    let item = if node.checkable {
+                        // 切换语言的菜单项始终反映当前的语言设置
+                        let checked = match &node.action {
+                            MenuCommand::SetLocale(locale) => *locale == self.locale,
+                            _ => state.checked.unwrap_or(node.checked),
+                        };
+                        MenuItemKind::Check(CheckMenuItem::with_id(
+                            self.app,
+                            &id,
+                            text,
+                            enabled,
+                            checked,
+                            accelerator,
+                        )?)
+                    } else {
+                        MenuItemKind::MenuItem(MenuItem::with_id(
+                            self.app,
+                            &id,
+                            text,
+                            enabled,
+                            accelerator,
+                        )?)
+                    };
+                    append(&item)?;
+                    self.handles.insert(id, item);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // 节点在运行时被修改过的状态
+    fn state(&self, node: &MenuNode) -> MenuItemState {
+        node.id
+            .as_ref()
+            .and_then(|id| self.overrides.get(id))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+// 最近文件菜单项的文字："1. 文件名 — 所在目录"
+// Windows 菜单中的 & 表示助记键，文件名中的 & 需要写成 &&
+fn recent_label(index: usize, path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_else(|| path.to_string_lossy());
+    let label = match path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => format!("{}. {} — {}", index + 1, name, dir.display()),
+        None => format!("{}. {}", index + 1, name),
+    };
+    label.replace('&', "&&")
+}
+
+// 按当前语言构建应用菜单栏和托盘菜单，并更新分发表
+pub fn build<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<(Menu<R>, Menu<R>)> {
+    let registry = app.state::<MenuRegistry>();
+    let mut builder = Builder {
+        app,
+        locale: app.state::<LocaleSettings>().selected(),
+        commands: HashMap::new(),
+        in_tray: false,
+        tray_items: HashSet::new(),
+        overrides: registry.overrides.read().unwrap().clone(),
+        handles: HashMap::new(),
+        accelerators: app.state::<Keymap>().accelerators(),
+    };
+
+    let app_menu = Menu::new(app)?;
+    builder.append_to(&registry.config.menu, &mut |item| app_menu.append(item))?;
+    let tray_menu = Menu::new(app)?;
+    builder.in_tray = true;
+    builder.append_to(&registry.config.tray, &mut |item| tray_menu.append(item))?;
+    for node in &registry.config.commands {
+        if let Some(id) = &node.id {
+            builder.commands.insert(id.clone(), node.action.clone());
+        }
+    }
+
+    *registry.commands.write().unwrap() = builder.commands;
+    *registry.tray_items.write().unwrap() = builder.tray_items;
+    match app.try_state::<MenuHandles<R>>() {
+        Some(handles) => *handles.items.write().unwrap() = builder.handles,
+        None => {
+            app.manage(MenuHandles {
+                items: RwLock::new(builder.handles),
+            });
+        }
+    }
+    Ok((app_menu, tray_menu))
+}
+
+// 按当前语言重建应用菜单和托盘菜单（包括托盘提示文字）
+pub fn refresh<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
+    let (app_menu, tray_menu) = build(app)?;
+    app.set_menu(app_menu)?;
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(tray_menu))?;
+        tray.set_tooltip(Some(t("tray.tooltip")))?;
+    }
+    Ok(())
+}
+
+fn item_not_found(id: &str) -> CommandError {
+    CommandError::NotFound {
+        resource: Resource::MenuItem,
+        id: id.to_string(),
+    }
+}
+
+// 读取菜单项（或带 ID 的子菜单）的当前状态；普通菜单项和子菜单的 checked 为 None
+pub fn item_state<R: Runtime>(app: &AppHandle<R>, id: &str) -> Result<MenuItemState, CommandError> {
+    let handles = app.state::<MenuHandles<R>>();
+    let items = handles.items.read().unwrap();
+    let state = match items.get(id) {
+        Some(MenuItemKind::MenuItem(item)) => MenuItemState {
+            enabled: Some(item.is_enabled()?),
+            text: Some(item.text()?),
+            checked: None,
+        },
+        Some(MenuItemKind::Check(item)) => MenuItemState {
+            enabled: Some(item.is_enabled()?),
+            text: Some(item.text()?),
+            checked: Some(item.is_checked()?),
+        },
+        Some(MenuItemKind::Submenu(item)) => MenuItemState {
+            enabled: Some(item.is_enabled()?),
+            text: Some(item.text()?),
+            checked: None,
+        },
+        _ => return Err(item_not_found(id)),
+    };
+    Ok(state)
+}
+
+// 启用 / 禁用、修改文字或勾选状态；应用菜单和托盘菜单中的菜单项都可以修改
+// 修改会被记住，切换语言重建菜单后仍然有效（修改过文字的菜单项不再随语言变化）
+pub fn update_item<R: Runtime>(
+    app: &AppHandle<R>,
+    id: &str,
+    update: &MenuItemState,
+) -> Result<MenuItemState, CommandError> {
+    {
+        let handles = app.state::<MenuHandles<R>>();
+        let items = handles.items.read().unwrap();
+        let item = items.get(id).ok_or_else(|| item_not_found(id))?;
+        if let (Some(checked), false) = (update.checked, matches!(item, MenuItemKind::Check(_))) {
+            return Err(CommandError::invalid_argument(
+                "checked",
+                checked,
+                "checkableMenuItem",
+            ));
+        }
+
+        match item {
+            MenuItemKind::MenuItem(item) => {
+                if let Some(enabled) = update.enabled {
+                    item.set_enabled(enabled)?;
+                }
+                if let Some(text) = &update.text {
+                    item.set_text(text)?;
+                }
+            }
+            MenuItemKind::Check(item) => {
+                if let Some(enabled) = update.enabled {
+                    item.set_enabled(enabled)?;
+                }
+                if let Some(text) = &update.text {
+                    item.set_text(text)?;
+                }
+                if let Some(checked) = update.checked {
+                    item.set_checked(checked)?;
+                }
+            }
+            MenuItemKind::Submenu(item) => {
+                if let Some(enabled) = update.enabled {
+                    item.set_enabled(enabled)?;
+                }
+                if let Some(text) = &update.text {
+                    item.set_text(text)?;
+                }
+            }
+            _ => return Err(item_not_found(id)),
+        }
+    }
+
+    app.state::<MenuRegistry>()
+        .overrides
+        .write()
+        .unwrap()
+        .entry(id.to_string())
+        .or_default()
+        .merge(update);
+    item_state(app, id)
+}
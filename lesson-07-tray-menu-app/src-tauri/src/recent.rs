@@ -0,0 +1,179 @@
+// 最近打开的文件：打开或保存文件时记录，显示在“文件 → 最近打开”子菜单中
+// 列表以 JSON 文件保存在应用数据目录下
+// 已经不存在的文件在读取列表时自动移除
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::atomic_write;
+use crate::error::{CommandError, IoOperation};
+
+// 默认保留的条数，以及允许设置的最大条数
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecentData {
+    #[serde(default = "default_limit")]
+    limit: usize,
+    // 最近使用的排在最前面
+    #[serde(default)]
+    paths: Vec<PathBuf>,
+}
+
+impl Default for RecentData {
+    fn default() -> Self {
+        RecentData {
+            limit: DEFAULT_LIMIT,
+            paths: Vec::new(),
+        }
+    }
+}
+
+fn default_limit() -> usize {
+    DEFAULT_LIMIT
+}
+
+pub struct RecentFiles {
+    file: PathBuf,
+    data: Mutex<RecentData>,
+}
+
+impl RecentFiles {
+    pub fn load(file: PathBuf) -> Self {
+        let mut data = fs::read_to_string(&file)
+            .ok()
+            .and_then(|text| match serde_json::from_str::<RecentData>(&text) {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    eprintln!("[RUST] 最近文件列表解析失败，将重新开始记录: {:?}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        // 手工改坏的上限恢复为默认值，而不是保留一个 set_limit 不接受的值
+        if !(1..=MAX_LIMIT).contains(&data.limit) {
+            eprintln!(
+                "[RUST] 最近文件的保留条数 {} 超出范围，恢复为默认值 {}",
+                data.limit, DEFAULT_LIMIT
+            );
+            data.limit = default_limit();
+        }
+        let limit = data.limit;
+        data.paths.truncate(limit);
+
+        RecentFiles {
+            file,
+            data: Mutex::new(data),
+        }
+    }
+
+    // 把当前列表原子地写回磁盘（见 atomic_write.rs）；写入失败只打印日志，不影响打开和保存文件
+    fn persist(&self, data: &RecentData) {
+        if let Some(dir) = self.file.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                eprintln!("[RUST] 创建最近文件目录失败: {:?}", e);
+                return;
+            }
+        }
+        match serde_json::to_string_pretty(data) {
+            Ok(text) => {
+                if let Err(e) = atomic_write::write_file(&self.file, text.as_bytes(), 0) {
+                    eprintln!("[RUST] 保存最近文件列表失败: {}", e);
+                }
+            }
+            Err(e) => eprintln!("[RUST] 序列化最近文件列表失败: {:?}", e),
+        }
+    }
+
+    // 当前列表，顺便移除已经不存在的文件
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let mut data = self.data.lock().unwrap();
+        let before = data.paths.len();
+        data.paths.retain(|path| path.is_file());
+        if data.paths.len() != before {
+            self.persist(&data);
+        }
+        data.paths.clone()
+    }
+
+    // 记录一个刚打开或保存的文件：移到列表最前面，超出上限时丢弃最旧的
+    pub fn add(&self, path: PathBuf) -> Result<Vec<PathBuf>, CommandError> {
+        fs::metadata(&path).map_err(|e| CommandError::io(IoOperation::Read, &path, e))?;
+        let mut data = self.data.lock().unwrap();
+        data.paths.retain(|existing| *existing != path);
+        data.paths.insert(0, path);
+        let limit = data.limit;
+        data.paths.truncate(limit);
+        self.persist(&data);
+        Ok(data.paths.clone())
+    }
+
+    pub fn clear(&self) {
+        let mut data = self.data.lock().unwrap();
+        data.paths.clear();
+        self.persist(&data);
+    }
+
+    // 修改保留的条数，列表超出新的上限时立即截断
+    pub fn set_limit(&self, limit: usize) -> Result<Vec<PathBuf>, CommandError> {
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(CommandError::invalid_argument(
+                "limit",
+                limit,
+                format!("1..={}", MAX_LIMIT),
+            ));
+        }
+        let mut data = self.data.lock().unwrap();
+        data.limit = limit;
+        data.paths.truncate(limit);
+        self.persist(&data);
+        Ok(data.paths.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 把内容写入临时目录下的独立文件，测试结束时删除
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("recent-{}-{}", std::process::id(), name));
+            fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn out_of_range_limit_is_reset_on_load() {
+        for (name, limit) in [("zero.json", 0), ("huge.json", 1000)] {
+            let paths: Vec<String> = (0..20).map(|i| format!("/tmp/{}.txt", i)).collect();
+            let text = serde_json::json!({ "limit": limit, "paths": paths }).to_string();
+            let file = TempFile::new(name, &text);
+            let recent = RecentFiles::load(file.0.clone());
+            let data = recent.data.lock().unwrap();
+            assert_eq!(data.limit, DEFAULT_LIMIT);
+            assert_eq!(data.paths.len(), DEFAULT_LIMIT);
+        }
+    }
+
+    #[test]
+    fn persists_through_atomic_write() {
+        let file = TempFile::new("persist.json", "{}");
+        let recent = RecentFiles::load(file.0.clone());
+        recent.set_limit(3).unwrap();
+        let reloaded = RecentFiles::load(file.0.clone());
+        assert_eq!(reloaded.data.lock().unwrap().limit, 3);
+    }
+}
@@ -0,0 +1,205 @@
+// 文本编码：打开文件时检测编码，保存时按文档的编码写回，编码转换由 encoding_rs 完成
+// 检测顺序：BOM → 没有 BOM 的 UTF-16（按 0 字节的分布判断）→ 合法的 UTF-8 →
+// chardetng 按字节统计猜测（GBK / GB18030、Big5、Shift_JIS、EUC-KR、windows-1252 等）
+// encoding_rs 只能解码 UTF-16、不能编码，UTF-16 的编码在这里单独处理
+use chardetng::EncodingDetector;
+use encoding_rs::{EncoderResult, Encoding, GB18030, GBK, UTF_16BE, UTF_16LE, UTF_8};
+use serde::Serialize;
+
+use crate::error::CommandError;
+
+// 判断没有 BOM 的 UTF-16 时检查的字节数
+const UTF16_SAMPLE: usize = 4096;
+// 错误信息中最多列出的不重复字符个数
+const ERROR_CHARACTERS: usize = 10;
+
+// 解码的结果
+pub struct Decoded {
+    pub text: String,
+    pub encoding: &'static Encoding,
+    // 文件开头是否有 BOM（已从 text 中去掉）
+    pub bom: bool,
+    // 有不符合该编码的字节，已替换为 U+FFFD，按原编码保存会丢失这些字节
+    pub malformed: bool,
+}
+
+// 目标编码无法表示的一个字符；位置按 UTF-16 计算，与编辑器一致，行号和列号从 1 开始
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Unrepresentable {
+    pub character: char,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+pub fn is_utf16(encoding: &'static Encoding) -> bool {
+    encoding == UTF_16LE || encoding == UTF_16BE
+}
+
+// 按名称查找可以用来保存的编码（名称不区分大小写，也接受 "gbk"、"sjis"、"utf-16" 等别名）
+// replacement 等只能解码的编码不能作为目标编码
+pub fn lookup(label: &str) -> Result<&'static Encoding, CommandError> {
+    Encoding::for_label(label.trim().as_bytes())
+        .filter(|encoding| is_utf16(encoding) || encoding.output_encoding() == *encoding)
+        .ok_or_else(|| CommandError::invalid_argument("encoding", label, "encodingLabel"))
+}
+
+// 检测编码并解码
+pub fn decode(bytes: &[u8]) -> Decoded {
+    let (encoding, bom_length) = Encoding::for_bom(bytes).unwrap_or_else(|| (detect(bytes), 0));
+    let (text, malformed) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+    // chardetng 把 GB18030 也报告为 GBK（两者的解码相同）；用到了 GBK 以外的四字节编码时
+    // 按 GB18030 保存，否则这些字符写不回去
+    let encoding = if encoding == GBK && !encode_lossy(&text, GBK, false).1.is_empty() {
+        GB18030
+    } else {
+        encoding
+    };
+    Decoded {
+        text: text.into_owned(),
+        encoding,
+        bom: bom_length > 0,
+        malformed,
+    }
+}
+
+// 没有 BOM 时推测编码
+fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some(encoding) = detect_utf16(bytes) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+// 没有 BOM 的 UTF-16：以 ASCII 为主的文本每两个字节中有一个是 0，
+// 0 在奇数位置为小端序，在偶数位置为大端序；普通文本文件中几乎不会出现 0 字节
+fn detect_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let sample = &bytes[..bytes.len().min(UTF16_SAMPLE)];
+    let units = sample.len() / 2;
+    let even = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+    if odd * 2 > units && even * 10 < units {
+        Some(UTF_16LE)
+    } else if even * 2 > units && odd * 10 < units {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+// 按 encoding 编码，bom 为 true 时在开头写入 BOM（只对 UTF-8 和 UTF-16 有效）
+// 有无法表示的字符时返回 Unrepresentable 错误，不做有损的替换
+pub fn encode(text: &str, encoding: &'static Encoding, bom: bool) -> Result<Vec<u8>, CommandError> {
+    let (bytes, unmappable) = encode_lossy(text, encoding, bom);
+    if unmappable.is_empty() {
+        return Ok(bytes);
+    }
+    let mut characters: Vec<char> = Vec::new();
+    for &(_, c) in &unmappable {
+        if !characters.contains(&c) && characters.len() < ERROR_CHARACTERS {
+            characters.push(c);
+        }
+    }
+    Err(CommandError::Unrepresentable {
+        encoding: encoding.name().to_string(),
+        count: unmappable.len(),
+        characters: characters.into_iter().collect(),
+    })
+}
+
+// text 中 encoding 无法表示的字符，按出现的先后排列
+pub fn unrepresentable(text: &str, encoding: &'static Encoding) -> Vec<Unrepresentable> {
+    let (_, unmappable) = encode_lossy(text, encoding, false);
+    let mut result = Vec::with_capacity(unmappable.len());
+    let mut pending = unmappable.into_iter().peekable();
+    let (mut offset, mut line, mut column) = (0, 1, 1);
+    for (index, c) in text.char_indices() {
+        let Some(&(position, character)) = pending.peek() else {
+            break;
+        };
+        if position == index {
+            result.push(Unrepresentable {
+                character,
+                offset,
+                line,
+                column,
+            });
+            pending.next();
+        }
+        offset += c.len_utf16();
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += c.len_utf16();
+        }
+    }
+    result
+}
+
+// 编码，无法表示的字符写为 '?'；同时返回这些字符在 text 中的字节位置
+fn encode_lossy(
+    text: &str,
+    encoding: &'static Encoding,
+    bom: bool,
+) -> (Vec<u8>, Vec<(usize, char)>) {
+    if is_utf16(encoding) {
+        let little = encoding == UTF_16LE;
+        let bom = bom.then_some(0xfeff);
+        let bytes = bom
+            .into_iter()
+            .chain(text.encode_utf16())
+            .flat_map(|unit| {
+                if little {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                }
+            })
+            .collect();
+        return (bytes, Vec::new());
+    }
+
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+    if bom && encoding == UTF_8 {
+        bytes.extend_from_slice(b"\xEF\xBB\xBF");
+    }
+    let mut unmappable = Vec::new();
+    let mut encoder = encoding.new_encoder();
+    let mut position = 0;
+    loop {
+        let rest = &text[position..];
+        bytes.reserve(
+            encoder
+                .max_buffer_length_from_utf8_without_replacement(rest.len())
+                .unwrap_or(rest.len()),
+        );
+        let (result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(rest, &mut bytes, true);
+        position += read;
+        match result {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::OutputFull => {}
+            // 无法表示的字符已经被读过
+            EncoderResult::Unmappable(c) => {
+                unmappable.push((position - c.len_utf8(), c));
+                bytes.push(b'?');
+            }
+        }
+    }
+    (bytes, unmappable)
+}
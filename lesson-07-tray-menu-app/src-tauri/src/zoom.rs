@@ -0,0 +1,229 @@
+// 视图缩放：每个窗口的缩放比例由 Rust 管理，通过 webview 的缩放接口生效，
+// 按窗口标签保存在应用数据目录下，重启后恢复；放大 / 缩小按固定的档位变化
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::CommandError;
+
+// 放大 / 缩小时依次经过的档位（百分比），与常见浏览器一致
+const STEPS: [u32; 17] = [
+    25, 33, 50, 67, 75, 80, 90, 100, 110, 125, 150, 175, 200, 250, 300, 400, 500,
+];
+const DEFAULT_PERCENT: u32 = 100;
+// 允许设置的缩放范围
+const LOWEST: u32 = 25;
+const HIGHEST: u32 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ZoomData {
+    // 缩放比例的下限和上限（百分比），档位和 set_zoom 都限制在这个范围内
+    #[serde(default = "default_min")]
+    min: u32,
+    #[serde(default = "default_max")]
+    max: u32,
+    // 窗口标签 -> 缩放比例，没有记录的窗口为 100%
+    #[serde(default)]
+    levels: BTreeMap<String, u32>,
+}
+
+impl Default for ZoomData {
+    fn default() -> Self {
+        ZoomData {
+            min: default_min(),
+            max: default_max(),
+            levels: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_min() -> u32 {
+    50
+}
+
+fn default_max() -> u32 {
+    300
+}
+
+// 某个窗口的缩放状态，也是 "zoom-changed" 事件的载荷
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoomLevel {
+    pub window: String,
+    pub percent: u32,
+    pub min: u32,
+    pub max: u32,
+}
+
+impl ZoomLevel {
+    // webview 缩放接口使用的比例，1.0 为 100%
+    pub fn scale(&self) -> f64 {
+        self.percent as f64 / 100.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoomStep {
+    In,
+    Out,
+    Reset,
+}
+
+pub struct ZoomSettings {
+    file: PathBuf,
+    data: Mutex<ZoomData>,
+}
+
+impl ZoomSettings {
+    pub fn load(file: PathBuf) -> Self {
+        let mut data = fs::read_to_string(&file)
+            .ok()
+            .and_then(|text| match serde_json::from_str::<ZoomData>(&text) {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    eprintln!("[RUST] 缩放设置解析失败，使用默认缩放: {:?}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        // 手动修改过的文件可能超出允许的范围
+        if check_range(data.min, data.max).is_err() {
+            eprintln!(
+                "[RUST] 缩放范围 {}% ~ {}% 无效，使用默认范围",
+                data.min, data.max
+            );
+            data.min = default_min();
+            data.max = default_max();
+        }
+        let (min, max) = (data.min, data.max);
+        for percent in data.levels.values_mut() {
+            *percent = (*percent).clamp(min, max);
+        }
+
+        ZoomSettings {
+            file,
+            data: Mutex::new(data),
+        }
+    }
+
+    // 把当前设置写回磁盘；写入失败只打印日志，缩放本身仍然生效
+    fn persist(&self, data: &ZoomData) {
+        if let Some(dir) = self.file.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                eprintln!("[RUST] 创建缩放设置目录失败: {:?}", e);
+                return;
+            }
+        }
+        match serde_json::to_string_pretty(data) {
+            Ok(text) => {
+                if let Err(e) = fs::write(&self.file, text) {
+                    eprintln!("[RUST] 保存缩放设置失败: {:?}", e);
+                }
+            }
+            Err(e) => eprintln!("[RUST] 序列化缩放设置失败: {:?}", e),
+        }
+    }
+
+    pub fn level(&self, window: &str) -> ZoomLevel {
+        let data = self.data.lock().unwrap();
+        level_of(&data, window)
+    }
+
+    // 设置窗口的缩放比例，超出范围时取最近的边界值
+    pub fn set(&self, window: &str, percent: u32) -> ZoomLevel {
+        let mut data = self.data.lock().unwrap();
+        let percent = percent.clamp(data.min, data.max);
+        self.update(&mut data, window, percent)
+    }
+
+    // 放大 / 缩小到下一个档位，或恢复 100%
+    pub fn step(&self, window: &str, step: ZoomStep) -> ZoomLevel {
+        let mut data = self.data.lock().unwrap();
+        let current = level_of(&data, window).percent;
+        let percent = match step {
+            ZoomStep::In => STEPS
+                .iter()
+                .copied()
+                .find(|&p| p > current)
+                .unwrap_or(HIGHEST),
+            ZoomStep::Out => STEPS
+                .iter()
+                .rev()
+                .copied()
+                .find(|&p| p < current)
+                .unwrap_or(LOWEST),
+            ZoomStep::Reset => DEFAULT_PERCENT,
+        };
+        let percent = percent.clamp(data.min, data.max);
+        self.update(&mut data, window, percent)
+    }
+
+    fn update(&self, data: &mut ZoomData, window: &str, percent: u32) -> ZoomLevel {
+        if percent == DEFAULT_PERCENT {
+            data.levels.remove(window);
+        } else {
+            data.levels.insert(window.to_string(), percent);
+        }
+        self.persist(data);
+        level_of(data, window)
+    }
+
+    // 修改允许的缩放范围，返回因此被调整过缩放比例的窗口
+    pub fn set_range(&self, min: u32, max: u32) -> Result<Vec<ZoomLevel>, CommandError> {
+        check_range(min, max)?;
+        let mut data = self.data.lock().unwrap();
+        data.min = min;
+        data.max = max;
+        let mut changed = Vec::new();
+        for (window, percent) in data.levels.iter_mut() {
+            let clamped = (*percent).clamp(min, max);
+            if clamped != *percent {
+                *percent = clamped;
+                changed.push(window.clone());
+            }
+        }
+        self.persist(&data);
+        Ok(changed
+            .iter()
+            .map(|window| level_of(&data, window))
+            .collect())
+    }
+}
+
+fn level_of(data: &ZoomData, window: &str) -> ZoomLevel {
+    let percent = data
+        .levels
+        .get(window)
+        .copied()
+        .unwrap_or(DEFAULT_PERCENT)
+        .clamp(data.min, data.max);
+    ZoomLevel {
+        window: window.to_string(),
+        percent,
+        min: data.min,
+        max: data.max,
+    }
+}
+
+// 范围必须在 25% ~ 500% 之内，并且包含 100%
+fn check_range(min: u32, max: u32) -> Result<(), CommandError> {
+    if !(LOWEST..=DEFAULT_PERCENT).contains(&min) {
+        return Err(CommandError::invalid_argument(
+            "min",
+            min,
+            format!("{}..={}", LOWEST, DEFAULT_PERCENT),
+        ));
+    }
+    if !(DEFAULT_PERCENT..=HIGHEST).contains(&max) {
+        return Err(CommandError::invalid_argument(
+            "max",
+            max,
+            format!("{}..={}", DEFAULT_PERCENT, HIGHEST),
+        ));
+    }
+    Ok(())
+}
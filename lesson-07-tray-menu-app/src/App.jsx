@@ -1,9 +1,8 @@
 import { useState, useEffect } from "react";
 import reactLogo from "./assets/react.svg";
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow, Window, LogicalSize } from "@tauri-apps/api/window";
-import { readTextFile, writeTextFile, readDir, exists, remove } from '@tauri-apps/plugin-fs';
+import { readDir, exists, remove } from '@tauri-apps/plugin-fs';
 import { open, save, message, ask } from '@tauri-apps/plugin-dialog';
 import { isPermissionGranted, requestPermission, sendNotification } from '@tauri-apps/plugin-notification';
 import "./App.css";
@@ -62,11 +61,15 @@ function App() {
   // 窗口控制相关状态
   const [windowTitle, setWindowTitle] = useState("主窗口");
   const [windowSize, setWindowSize] = useState("1200x800");
+  const [zoomPercent, setZoomPercent] = useState(100);
   const [isSecondaryVisible, setIsSecondaryVisible] = useState(false);
 
   // 文件系统相关状态
   const [fileContent, setFileContent] = useState("");
   const [currentFile, setCurrentFile] = useState(null);
+  const [documentId, setDocumentId] = useState(null); // Rust 文档模型中的文档 ID（负责撤销 / 重做）
+  const [fileEncoding, setFileEncoding] = useState("UTF-8"); // 打开时检测到的编码，保存时按这个编码写回
+  const [lineEnding, setLineEnding] = useState("lf"); // 保存时使用的换行符："lf" 或 "crlf"
   const [directoryEntries, setDirectoryEntries] = useState([]);
   const [currentDirectory, setCurrentDirectory] = useState(null);
   const [fileExists, setFileExists] = useState(null);
@@ -120,8 +123,8 @@ function App() {
       
       setResult(`计算结果：${a} ${operationSymbols[operation]} ${b} = ${calculatedResult}`);
     } catch (err) {
-      // 捕获Rust后端返回的错误（如除零错误）
-      setError(err || "计算过程中发生错误！");
+      // 捕获Rust后端返回的错误（如除零错误），结构为 { code, message, details }
+      setError(err?.message || "计算过程中发生错误！");
       setResult("");
     }
   }
@@ -213,7 +216,7 @@ function App() {
       setDivideError("");
     } catch (err) {
       // 捕获Rust后端返回的错误（Result的Err部分）
-      setDivideError(`错误：${err}`);
+      setDivideError(`错误：${err?.message ?? err}`);
       setDivideResult("");
     }
   }
@@ -322,23 +325,139 @@ function App() {
 
   // ========== 文件系统操作函数 ==========
 
-  // 打开文件对话框并读取文件
-  async function handleOpenFile() {
+  // 显示 Rust 文档服务返回的文档：{ id, path, encoding, lineEnding, dirty, text, ... }
+  function showDocument(doc) {
+    setDocumentId(doc.id);
+    setFileContent(doc.text);
+    setCurrentFile(doc.path);
+    setFileEncoding(doc.encoding);
+    setLineEnding(doc.lineEnding);
+    if (doc.mixedLineEndings) {
+      const { lf, crlf, cr } = doc.mixedLineEndings;
+      message(`文件混用了多种换行符（LF ${lf} 处，CRLF ${crlf} 处，CR ${cr} 处），保存时将统一为 ${doc.lineEnding.toUpperCase()}`, { title: "换行符", kind: "info" });
+    }
+    if (doc.malformed) {
+      message(`文件中有不符合 ${doc.encoding} 编码的字节，已显示为 �，保存后这些字节会丢失`, { title: "编码警告", kind: "warning" });
+    }
+  }
+
+  // 转换编码（下次保存时生效）；结果中列出新编码无法表示的字符，需要删除后才能保存
+  async function handleConvertEncoding(encoding) {
+    if (documentId === null) return;
     try {
-      console.log("打开文件对话框...");
-      const selected = await open({
-        multiple: false,
-        filters: [
-          { name: '文本文件', extensions: ['txt', 'md'] },
-          { name: '所有文件', extensions: ['*'] }
-        ]
-      });
+      const result = await invoke("convert_document_encoding", { id: documentId, encoding });
+      setFileEncoding(result.encoding);
+      if (result.unrepresentableCount > 0) {
+        const samples = result.unrepresentable
+          .slice(0, 5)
+          .map((item) => `“${item.character}”（第 ${item.line} 行第 ${item.column} 列）`)
+          .join("\n");
+        await message(`有 ${result.unrepresentableCount} 个字符无法用 ${result.encoding} 表示，删除或替换后才能保存：\n\n${samples}`, {
+          title: "编码警告",
+          kind: "warning",
+        });
+      }
+    } catch (err) {
+      console.error("转换编码失败:", err);
+      alert(`转换编码失败: ${err?.message || err}`);
+    }
+  }
+
+  // 在 Rust 中新建文档，编辑器的内容和撤销历史从此由文档模型管理
+  async function loadDocument(text) {
+    try {
+      showDocument(await invoke("create_document", { text }));
+    } catch (err) {
+      console.error("创建文档失败:", err);
+      setFileContent(text);
+    }
+  }
+
+  // 上次运行异常退出时留下的恢复快照：逐个询问是否恢复，不恢复的快照丢弃
+  // 快照为 { id, path, timestamp, length, preview }
+  async function offerRecovery() {
+    try {
+      const snapshots = await invoke("list_recovery_snapshots");
+      for (const snapshot of snapshots) {
+        const time = new Date(snapshot.timestamp * 1000).toLocaleString();
+        const confirmed = await ask(
+          `发现上次未保存的修改：\n\n${snapshot.path ?? "未命名文档"}\n${time}，共 ${snapshot.length} 个字符\n${snapshot.preview}\n\n是否恢复？`,
+          { title: '恢复未保存的修改', kind: 'warning', okLabel: '恢复', cancelLabel: '丢弃' }
+        );
+        if (confirmed) {
+          showDocument(await invoke("recover_snapshot", { id: snapshot.id }));
+        } else {
+          await invoke("discard_recovery_snapshot", { id: snapshot.id });
+        }
+      }
+    } catch (err) {
+      console.error("恢复未保存的修改失败:", err);
+    }
+  }
+
+  // 整理文档（转换换行符、删除行尾空白、补上末尾换行），每项操作都可以撤销
+  async function handleTidyDocument(command, args = {}) {
+    if (documentId === null) return;
+    try {
+      const change = await invoke(command, { id: documentId, ...args });
+      setFileContent(change.text);
+      setLineEnding(change.lineEnding);
+    } catch (err) {
+      console.error("整理文档失败:", err);
+      alert(`整理文档失败: ${err?.message || err}`);
+    }
+  }
+
+  // 编辑器内容变化：找出与上次内容不同的部分，作为一次替换发给文档模型
+  function handleEditorChange(e) {
+    const next = e.target.value;
+    const prev = fileContent;
+    setFileContent(next);
+    if (documentId === null) return;
+
+    let start = 0;
+    while (start < prev.length && start < next.length && prev[start] === next[start]) start++;
+    let end = 0;
+    while (end < prev.length - start && end < next.length - start
+      && prev[prev.length - 1 - end] === next[next.length - 1 - end]) end++;
+    const edits = [{
+      kind: "replace",
+      offset: start,
+      length: prev.length - start - end,
+      text: next.slice(start, next.length - end),
+    }];
+    invoke("edit_document", { id: documentId, edits }).catch((err) => {
+      console.error("同步文档修改失败:", err.message);
+    });
+  }
+
+  // 编辑器的选区变化时告诉文档模型，“剪切”“复制”菜单项由 Rust 直接读取选中的文字
+  function handleEditorSelect(e) {
+    if (documentId === null) return;
+    const { selectionStart: start, selectionEnd: end } = e.target;
+    invoke("set_document_selection", { id: documentId, start, end }).catch((err) => {
+      console.error("同步选区失败:", err.message);
+    });
+  }
+
+  // 打开文件对话框，由 Rust 读取文件（同时记入“最近打开”）；传入 path 时直接打开
+  async function handleOpenFile(path) {
+    try {
+      let selected = path;
+      if (!selected) {
+        console.log("打开文件对话框...");
+        selected = await open({
+          multiple: false,
+          filters: [
+            { name: '文本文件', extensions: ['txt', 'md'] },
+            { name: '所有文件', extensions: ['*'] }
+          ]
+        });
+      }
 
       if (selected) {
         console.log("选择的文件:", selected);
-        const content = await readTextFile(selected);
-        setFileContent(content);
-        setCurrentFile(selected);
+        showDocument(await invoke("open_document", { path: selected }));
         console.log("文件读取成功");
       } else {
         console.log("用户取消了文件选择");
@@ -353,10 +472,10 @@ function App() {
   // 保存文件（任务6：结合通知）
   async function handleSaveFile() {
     try {
-      if (currentFile) {
-        // 保存到当前文件
+      if (currentFile && documentId !== null) {
+        // 保存到当前文件，由 Rust 按原来的编码和换行符写入
         console.log("保存到当前文件:", currentFile);
-        await writeTextFile(currentFile, fileContent);
+        await invoke("save_document", { id: documentId });
         console.log("文件保存成功");
         // 任务6：成功后发送通知
         await sendSuccessNotification(`文件已保存：${currentFile}`);
@@ -388,8 +507,8 @@ function App() {
       if (path) {
         console.log("保存路径:", path);
         try {
-          await writeTextFile(path, fileContent);
-          setCurrentFile(path);
+          const info = await invoke("save_document_as", { id: documentId, path });
+          setCurrentFile(info.path);
           console.log("文件另存为成功");
           // 任务6：成功后发送通知
           await sendSuccessNotification(`文件已另存为：${path}`);
@@ -707,8 +826,11 @@ function App() {
         await remove(currentFile);
         console.log("文件删除成功:", currentFile);
         
-        // 清空文件内容
-        setFileContent("");
+        // 关闭已删除文件的文档，换成一个空文档
+        if (documentId !== null) {
+          await invoke("close_document", { id: documentId, discard: true }).catch(() => {});
+        }
+        await loadDocument("");
         setCurrentFile(null);
         
         // 发送成功通知
@@ -803,52 +925,50 @@ function App() {
       }
     };
     
+    // 剪切 / 复制 / 粘贴菜单项作用于拥有焦点的输入框，或页面中选中的文字
+    const isEditable = (el) => el && (el.tagName === "INPUT" || el.tagName === "TEXTAREA") && !el.readOnly;
+    const selectedText = () => {
+      const el = document.activeElement;
+      if (isEditable(el) && el.selectionStart !== el.selectionEnd) {
+        return el.value.slice(el.selectionStart, el.selectionEnd);
+      }
+      return window.getSelection()?.toString() ?? "";
+    };
+    
     // 监听菜单事件
     const setupMenuListeners = async () => {
       try {
-        // 监听菜单操作事件
-        const unlisten = await listen("menu-action", (event) => {
-          const action = event.payload;
-          console.log("菜单操作:", action);
+        // 监听菜单操作事件：Rust 只把事件发给拥有焦点的窗口，这里只接收发给当前窗口的事件
+        // 载荷为 { action, window, source: "click" | "accelerator" | "tray" | "palette", clipboard?, timestamp }
+        const unlisten = await getCurrentWindow().listen("menu-action", (event) => {
+          const { action, source, clipboard } = event.payload;
+          console.log("菜单操作:", action, "来源:", source);
           
           // 根据不同的菜单操作显示相应的对话框
           switch (action) {
-            case "new":
-              message("新建文件", { title: "文件菜单", kind: "info" });
-              break;
-            case "open":
-              message("打开文件", { title: "文件菜单", kind: "info" });
-              break;
-            case "save":
-              message("保存文件", { title: "文件菜单", kind: "info" });
-              break;
-            case "save_as":
-              message("另存为", { title: "文件菜单", kind: "info" });
-              break;
-            case "undo":
-              message("撤销操作", { title: "编辑菜单", kind: "info" });
-              break;
-            case "redo":
-              message("重做操作", { title: "编辑菜单", kind: "info" });
-              break;
             case "cut":
-              message("剪切", { title: "编辑菜单", kind: "info" });
-              break;
-            case "copy":
-              message("复制", { title: "编辑菜单", kind: "info" });
-              break;
-            case "paste":
-              message("粘贴", { title: "编辑菜单", kind: "info" });
-              break;
-            case "zoom_in":
-              message("放大视图", { title: "视图菜单", kind: "info" });
+            case "copy": {
+              // 编辑器中选中的文字已由 Rust 处理，这里只处理其他输入框和页面中选中的文字，
+              // 同样通过 Rust 写入剪贴板，记入剪贴板历史
+              const text = selectedText();
+              if (!text) break;
+              invoke("write_clipboard_text", { text })
+                .then(() => {
+                  if (action === "cut" && isEditable(document.activeElement)) {
+                    document.execCommand("delete");
+                  }
+                })
+                .catch((err) => console.error("写入剪贴板失败:", err.message));
               break;
-            case "zoom_out":
-              message("缩小视图", { title: "视图菜单", kind: "info" });
-              break;
-            case "zoom_reset":
-              message("重置缩放", { title: "视图菜单", kind: "info" });
+            }
+            case "paste": {
+              // Rust 已经读取了剪贴板，clipboard 为 { kind: "text" | "html" | "image", ... }
+              const text = clipboard?.kind === "html" ? clipboard.text : clipboard?.text;
+              if (text && isEditable(document.activeElement)) {
+                document.execCommand("insertText", false, text);
+              }
               break;
+            }
             case "about":
               message("我的TAURI学习应用\n版本：1.0.0\n\n这是一个用于学习TAURI框架的示例应用。", {
                 title: "关于",
@@ -860,20 +980,67 @@ function App() {
           }
         });
         
+        // 视图缩放由 Rust 管理并直接作用于 webview，这里只同步显示的百分比
+        // 载荷为 { window, percent, min, max }
+        const unlistenZoom = await getCurrentWindow().listen("zoom-changed", (event) => {
+          setZoomPercent(event.payload.percent);
+        });
+        invoke("get_zoom").then((level) => setZoomPercent(level.percent)).catch(() => {});
+        
+        // 撤销 / 重做由 Rust 的文档模型执行，结果通过 "document-changed" 事件发回
+        // 载荷为 { id, text, version, canUndo, canRedo, dirty, changes }
+        const unlistenDocument = await getCurrentWindow().listen("document-changed", (event) => {
+          setFileContent(event.payload.text);
+          setLineEnding(event.payload.lineEnding);
+        });
+        // 新建空文档后检查上次运行留下的恢复快照（只在主窗口询问）
+        loadDocument("").then(() => {
+          if (getCurrentWindow().label === "main") offerRecovery();
+        });
+        
+        // 文件菜单（新建、打开、最近打开、保存、另存为）由 Rust 的文档服务直接处理，结果通过事件发回
+        const unlistenOpened = await getCurrentWindow().listen("document-opened", (event) => {
+          showDocument(event.payload);
+        });
+        const unlistenSaved = await getCurrentWindow().listen("document-saved", (event) => {
+          setCurrentFile(event.payload.path);
+          sendSuccessNotification(`文件已保存：${event.payload.path}`);
+        });
+        const unlistenError = await getCurrentWindow().listen("document-error", (event) => {
+          message(event.payload.message, { title: "文件操作失败", kind: "error" });
+        });
+        
         // 返回清理函数
         return () => {
           unlisten();
+          unlistenZoom();
+          unlistenDocument();
+          unlistenOpened();
+          unlistenSaved();
+          unlistenError();
         };
       } catch (err) {
         console.error("设置菜单监听器失败:", err);
       }
     };
     
+    // 上报修饰键状态，Rust 据此判断菜单动作是点击还是快捷键触发的
+    const isModifier = (key) => ["Control", "Meta", "Alt", "Shift"].includes(key);
+    const reportModifiers = (event) => {
+      if (!isModifier(event.key)) return;
+      const held = event.ctrlKey || event.metaKey || event.altKey || event.shiftKey;
+      invoke("report_modifiers", { held }).catch(() => {});
+    };
+    window.addEventListener("keydown", reportModifiers);
+    window.addEventListener("keyup", reportModifiers);
+    
     setupWindowListeners();
     const cleanup = setupMenuListeners();
     
     // 清理函数
     return () => {
+      window.removeEventListener("keydown", reportModifiers);
+      window.removeEventListener("keyup", reportModifiers);
       if (cleanup) {
         cleanup.then((unlisten) => {
           if (unlisten) unlisten();
@@ -1104,7 +1271,7 @@ function App() {
         
         {/* 当前窗口信息 */}
         <p style={{ marginTop: "1rem", fontSize: "0.9em", color: "#666" }}>
-          当前窗口大小：{windowSize}
+          当前窗口大小：{windowSize}，缩放：{zoomPercent}%
         </p>
       </div>
 
@@ -1116,7 +1283,7 @@ function App() {
         <div style={{ marginBottom: "2rem" }}>
           <h3 style={{ marginBottom: "0.5rem" }}>文件操作</h3>
           <div className="row" style={{ marginBottom: "1rem" }}>
-            <button type="button" onClick={handleOpenFile}>
+            <button type="button" onClick={() => handleOpenFile()}>
               打开文件
             </button>
             <button type="button" onClick={handleSaveFile}>
@@ -1137,10 +1304,31 @@ function App() {
               当前文件: {currentFile}
             </p>
           )}
+          <p style={{ fontSize: "0.9em", color: "#666", marginBottom: "0.5rem" }}>
+            编码:{" "}
+            <select value={fileEncoding} onChange={(e) => handleConvertEncoding(e.target.value)}>
+              {[...new Set([fileEncoding, "UTF-8", "gb18030", "GBK", "Big5", "Shift_JIS", "EUC-KR", "UTF-16LE", "UTF-16BE"])].map((name) => (
+                <option key={name} value={name}>{name}</option>
+              ))}
+            </select>
+            {" "}换行符:{" "}
+            <select value={lineEnding} onChange={(e) => handleTidyDocument("convert_line_endings", { lineEnding: e.target.value })}>
+              <option value="lf">LF</option>
+              <option value="crlf">CRLF</option>
+            </select>
+            {" "}
+            <button type="button" onClick={() => handleTidyDocument("trim_trailing_whitespace")}>
+              删除行尾空白
+            </button>
+            <button type="button" onClick={() => handleTidyDocument("ensure_final_newline")}>
+              补上末尾换行
+            </button>
+          </p>
           
           <textarea
             value={fileContent}
-            onChange={(e) => setFileContent(e.target.value)}
+            onChange={handleEditorChange}
+            onSelect={handleEditorSelect}
             placeholder="文件内容将显示在这里..."
             style={{
               width: "100%",
@@ -1,14 +1,21 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-use serde::{Deserialize, Serialize};
+use tauri::ipc::Channel;
+use tauri::State;
 
-// 统计信息结构体
-#[derive(Serialize, Deserialize)]
-struct Statistics {
-    sum: f64,
-    average: f64,
-    max: f64,
-    min: f64,
-}
+// 计算器和统计功能与 lesson-07-tray-menu-app 共用，代码在 app-core 中
+use app_core::{csv_import, decimal, error, expression, histogram, history, i18n, outliers, regression, statistics, stream_sessions, stream_stats};
+use app_core::current_timestamp;
+
+use csv_import::{CsvAnalysis, CsvImportOptions};
+use error::{CommandError, ValueSource};
+use histogram::{Histogram, HistogramOptions};
+use history::{HistoryEntry, HistoryStore, Operation};
+use i18n::Locale;
+use outliers::{OutlierOptions, OutlierReport};
+use regression::{FittedModel, Prediction, RegressionOptions, RegressionResult};
+use statistics::{Statistics, StatisticsOptions};
+use stream_sessions::StreamSessions;
+use stream_stats::{StreamOptions, StreamStatistics};
 
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -18,11 +25,7 @@ fn greet(name: &str) -> String {
 // 获取当前时间戳（Unix时间戳，秒）
 #[tauri::command]
 fn get_timestamp() -> u64 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
+    current_timestamp()
 }
 
 // 获取系统信息
@@ -31,54 +34,152 @@ fn get_system_info() -> String {
     format!("操作系统类型: {}", std::env::consts::OS)
 }
 
-// 安全除法：正确处理除零错误
+// 设置错误提示使用的语言（"zh-CN" / "en-US"），默认根据系统语言推断（见 i18n.rs）
+// 错误码 code 与语言无关，前端应根据它而不是 message 做判断
 #[tauri::command]
-fn safe_divide(a: f64, b: f64) -> Result<f64, String> {
+fn set_locale(locale: Locale) {
+    Locale::set_current(locale);
+}
+
+// 安全除法的计算逻辑：正确处理除零错误
+fn divide_values(a: f64, b: f64) -> Result<f64, CommandError> {
     if b == 0.0 {
-        Err("除数不能为零！".to_string())
+        Err(CommandError::DivisionByZero)
     } else {
         Ok(a / b)
     }
 }
 
-// 处理数字数组，返回统计信息
+// 安全除法：结果会记录到计算历史
 #[tauri::command]
-fn process_numbers(numbers: Vec<f64>) -> Statistics {
-    if numbers.is_empty() {
-        return Statistics {
-            sum: 0.0,
-            average: 0.0,
-            max: 0.0,
-            min: 0.0,
-        };
-    }
-    
-    let sum: f64 = numbers.iter().sum();
-    let average = sum / numbers.len() as f64;
-    let max = numbers.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    let min = numbers.iter().cloned().fold(f64::INFINITY, f64::min);
-    
-    Statistics {
-        sum,
-        average,
-        max,
-        min,
-    }
+fn safe_divide(a: f64, b: f64, history: State<'_, HistoryStore>) -> Result<f64, CommandError> {
+    let result = divide_values(a, b);
+    history.record(Operation::SafeDivide { a, b }, &result);
+    result
 }
 
-// 计算器命令：接收运算类型和两个数字，返回计算结果
-// 使用Result类型来处理错误（特别是除零错误）
+// 处理数字数组，返回描述性统计信息
+// options 可指定额外的百分位数，以及 NaN / 无穷大的处理策略（reject / skip / propagate）
+// 空数组会返回错误，而不是一组全零的统计值
+#[tauri::command]
+fn process_numbers(
+    numbers: Vec<f64>,
+    options: Option<StatisticsOptions>,
+    history: State<'_, HistoryStore>,
+) -> Result<Statistics, CommandError> {
+    let options = options.unwrap_or_default();
+    let result = statistics::compute(&numbers, &options);
+    history.record(Operation::process_numbers(numbers, options), &result);
+    result
+}
+
+// 离群值检测命令：支持 Z 分数、修正 Z 分数（MAD）和 IQR 栅栏三种方法，
+// 返回被标记的下标和数值，以及剔除离群值后重新计算的统计信息
 #[tauri::command]
-fn calculate(operation: &str, a: f64, b: f64) -> Result<f64, String> {
+fn detect_outliers(
+    numbers: Vec<f64>,
+    options: Option<OutlierOptions>,
+) -> Result<OutlierReport, CommandError> {
+    outliers::detect(&numbers, &options.unwrap_or_default())
+}
+
+// 直方图命令：返回箱边界和计数，前端直接用来画图
+// options.bins 支持固定箱数、固定箱宽以及 Sturges / Scott / Freedman–Diaconis 自动规则，
+// cumulative 和 density 控制累积与密度归一化
+#[tauri::command]
+fn compute_histogram(
+    numbers: Vec<f64>,
+    options: Option<HistogramOptions>,
+) -> Result<Histogram, CommandError> {
+    histogram::compute(&numbers, &options.unwrap_or_default())
+}
+
+// 回归分析命令：对成对的 x / y 数据拟合线性、多项式、指数或对数模型，
+// 返回系数、R²、残差，以及 options.predict 中各 x 处的预测值
+#[tauri::command]
+fn fit_regression(
+    x: Vec<f64>,
+    y: Vec<f64>,
+    options: Option<RegressionOptions>,
+) -> Result<RegressionResult, CommandError> {
+    regression::fit(&x, &y, &options.unwrap_or_default())
+}
+
+// 用 fit_regression 返回的模型在新的 x 处做预测
+#[tauri::command]
+fn predict_regression(model: FittedModel, x: Vec<f64>) -> Result<Vec<Prediction>, CommandError> {
+    regression::predict(&model, &x)
+}
+
+// 导入 CSV / TSV 文件并按列统计：自动识别分隔符和表头，
+// options.columns 可按列名或列序号选择要统计的列，无法解析的单元格会带行号返回
+#[tauri::command]
+fn analyze_csv(path: &str, options: Option<CsvImportOptions>) -> Result<CsvAnalysis, CommandError> {
+    csv_import::analyze(std::path::Path::new(path), &options.unwrap_or_default())
+}
+
+// ========== 流式统计 ==========
+// 适合传感器等海量数据：分多次推送，随时查询当前统计，内存占用不随样本数增长
+
+// 打开一个流式统计会话，返回会话 ID
+#[tauri::command]
+fn open_stats_session(
+    options: Option<StreamOptions>,
+    sessions: State<'_, StreamSessions>,
+) -> Result<u64, CommandError> {
+    sessions.open(&options.unwrap_or_default())
+}
+
+// 向会话推送一批数字，返回推送后的统计结果
+#[tauri::command]
+fn push_stats_batch(
+    session_id: u64,
+    numbers: Vec<f64>,
+    sessions: State<'_, StreamSessions>,
+) -> Result<StreamStatistics, CommandError> {
+    sessions.push(session_id, &numbers)
+}
+
+// 查询会话当前的统计结果
+#[tauri::command]
+fn get_session_statistics(
+    session_id: u64,
+    sessions: State<'_, StreamSessions>,
+) -> Result<StreamStatistics, CommandError> {
+    sessions.snapshot(session_id)
+}
+
+// 订阅会话：之后每推送一批数据，都会通过 Channel 把最新统计发给前端
+#[tauri::command]
+fn subscribe_stats_session(
+    session_id: u64,
+    on_update: Channel<StreamStatistics>,
+    sessions: State<'_, StreamSessions>,
+) -> Result<(), CommandError> {
+    sessions.subscribe(session_id, on_update)
+}
+
+// 关闭会话，返回最终统计结果（会话中没有数据时为 null）
+#[tauri::command]
+fn close_stats_session(
+    session_id: u64,
+    sessions: State<'_, StreamSessions>,
+) -> Result<Option<StreamStatistics>, CommandError> {
+    sessions.close(session_id)
+}
+
+// 计算器的计算逻辑：接收运算类型和两个数字，返回计算结果
+// 使用Result类型来处理错误（特别是除零错误）
+fn calculate_values(operation: &str, a: f64, b: f64) -> Result<f64, CommandError> {
     // 验证输入是否为有效数字
     if a.is_nan() || b.is_nan() {
-        return Err("输入的数字无效".to_string());
+        return Err(CommandError::InvalidNumber { index: None, value: None });
     }
-    
+
     if a.is_infinite() || b.is_infinite() {
-        return Err("输入的数字超出范围".to_string());
+        return Err(CommandError::OutOfRange { source: ValueSource::Input });
     }
-    
+
     // 根据运算类型执行相应的计算
     match operation {
         "add" => Ok(a + b),
@@ -87,23 +188,173 @@ fn calculate(operation: &str, a: f64, b: f64) -> Result<f64, String> {
         "divide" => {
             // 处理除零错误
             if b == 0.0 {
-                Err("除数不能为零！".to_string())
+                Err(CommandError::DivisionByZero)
             } else {
                 Ok(a / b)
             }
         },
-        _ => Err(format!("不支持的运算类型: {}", operation))
+        _ => Err(CommandError::UnsupportedOperation { operation: operation.to_string() })
     }
 }
 
+// 计算器命令：结果会记录到计算历史
+#[tauri::command]
+fn calculate(
+    operation: &str,
+    a: f64,
+    b: f64,
+    history: State<'_, HistoryStore>,
+) -> Result<f64, CommandError> {
+    let result = calculate_values(operation, a, b);
+    history.record(
+        Operation::Calculate {
+            operation: operation.to_string(),
+            a,
+            b,
+        },
+        &result,
+    );
+    result
+}
+
+// 表达式求值命令：支持运算优先级、括号、一元负号、乘方(^)、取模(%)
+// 以及 sin、cos、ln、sqrt、abs 等常用函数
+// 出错时 details 中带有 column / length，前端可以据此标出出错的位置
+#[tauri::command]
+fn evaluate_expression(
+    expression: &str,
+    history: State<'_, HistoryStore>,
+) -> Result<f64, CommandError> {
+    let result = expression::evaluate(expression);
+    history.record(
+        Operation::EvaluateExpression {
+            expression: expression.to_string(),
+        },
+        &result,
+    );
+    result
+}
+
+// 十进制高精度计算命令：a、b 以字符串传入，结果以精确的十进制字符串返回
+// calculate 仍然保持 f64 的行为，需要精确结果（如金额）时按次改用此命令
+// options 可指定保留的小数位数（precision）和舍入模式（halfEven / halfUp / truncate）
+#[tauri::command]
+fn calculate_decimal(
+    operation: &str,
+    a: &str,
+    b: &str,
+    options: Option<decimal::DecimalOptions>,
+    history: State<'_, HistoryStore>,
+) -> Result<String, CommandError> {
+    let options = options.unwrap_or_default();
+    let result = decimal::calculate(operation, a, b, options);
+    history.record(
+        Operation::CalculateDecimal {
+            operation: operation.to_string(),
+            a: a.to_string(),
+            b: b.to_string(),
+            options,
+        },
+        &result,
+    );
+    result
+}
+
+// ========== 计算历史 ==========
+
+// 按时间倒序列出历史记录
+#[tauri::command]
+fn list_history(limit: Option<usize>, history: State<'_, HistoryStore>) -> Vec<HistoryEntry> {
+    history.list(limit)
+}
+
+// 按关键字搜索历史记录（匹配运算类型、输入、结果和错误信息）
+#[tauri::command]
+fn search_history(
+    query: &str,
+    limit: Option<usize>,
+    history: State<'_, HistoryStore>,
+) -> Vec<HistoryEntry> {
+    history.search(query, limit)
+}
+
+// 删除一条历史记录，返回是否删除成功
+#[tauri::command]
+fn delete_history_entry(id: u64, history: State<'_, HistoryStore>) -> bool {
+    history.delete(id)
+}
+
+// 清空全部历史记录
+#[tauri::command]
+fn clear_history(history: State<'_, HistoryStore>) {
+    history.clear();
+}
+
+// 重放一条历史记录：用原来的输入重新计算一次，并作为新的历史条目返回
+#[tauri::command]
+fn replay_history_entry(id: u64, history: State<'_, HistoryStore>) -> Result<HistoryEntry, CommandError> {
+    let entry = history
+        .get(id)
+        .ok_or_else(|| CommandError::NotFound {
+            resource: error::Resource::HistoryEntry,
+            id: id.to_string(),
+        })?;
+
+    // 输入过多、只保存了一部分的记录无法按原样重放
+    if !entry.operation.is_complete() {
+        return Err(CommandError::invalid_argument("id", id, "completeHistoryEntry"));
+    }
+
+    let operation = entry.operation.clone();
+    let replayed = match &entry.operation {
+        Operation::Calculate { operation: op, a, b } => {
+            history.record(operation, &calculate_values(op, *a, *b))
+        }
+        Operation::SafeDivide { a, b } => history.record(operation, &divide_values(*a, *b)),
+        Operation::ProcessNumbers { numbers, options, .. } => {
+            history.record(operation, &statistics::compute(numbers, options))
+        }
+        Operation::EvaluateExpression { expression } => {
+            history.record(operation, &expression::evaluate(expression))
+        }
+        Operation::CalculateDecimal {
+            operation: op,
+            a,
+            b,
+            options,
+        } => history.record(operation, &decimal::calculate(op, a, b, *options)),
+    };
+    Ok(replayed)
+}
+
+// 导出历史记录到文件，format 支持 "json"（默认）和 "csv"，返回导出的条数
+#[tauri::command]
+fn export_history(
+    path: &str,
+    format: Option<String>,
+    history: State<'_, HistoryStore>,
+) -> Result<usize, CommandError> {
+    let format = format.unwrap_or_else(|| "json".to_string());
+    history.export(std::path::Path::new(path), &format.to_lowercase())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    use tauri::Manager;
+
     tauri::Builder::default()
+        .setup(|app| {
+            // 计算历史保存在应用数据目录下，重启后仍然可用
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(HistoryStore::load(data_dir.join("history.json")));
+            app.manage(StreamSessions::default());
+            Ok(())
+        })
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())      // 注册文件系统插件
         .plugin(tauri_plugin_dialog::init())  // 注册对话框插件
         .plugin(tauri_plugin_notification::init())  // 注册通知插件
-        .invoke_handler(tauri::generate_handler![greet, calculate, get_timestamp, get_system_info, process_numbers, safe_divide])
+        .invoke_handler(tauri::generate_handler![greet, calculate, get_timestamp, get_system_info, process_numbers, safe_divide, evaluate_expression, calculate_decimal, list_history, search_history, delete_history_entry, clear_history, replay_history_entry, export_history, open_stats_session, push_stats_batch, get_session_statistics, subscribe_stats_session, close_stats_session, analyze_csv, compute_histogram, fit_regression, predict_regression, detect_outliers, set_locale])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -119,8 +119,8 @@ function App() {
       
       setResult(`计算结果：${a} ${operationSymbols[operation]} ${b} = ${calculatedResult}`);
     } catch (err) {
-      // 捕获Rust后端返回的错误（如除零错误）
-      setError(err || "计算过程中发生错误！");
+      // 捕获Rust后端返回的错误（如除零错误），结构为 { code, message, details }
+      setError(err?.message || "计算过程中发生错误！");
       setResult("");
     }
   }
@@ -212,7 +212,7 @@ function App() {
       setDivideError("");
     } catch (err) {
       // 捕获Rust后端返回的错误（Result的Err部分）
-      setDivideError(`错误：${err}`);
+      setDivideError(`错误：${err?.message ?? err}`);
       setDivideResult("");
     }
   }